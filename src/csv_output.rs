@@ -0,0 +1,242 @@
+// flattens every export type into one row layout for spreadsheets
+// fields that don't apply to an event type are left empty
+
+use superchat_extractor::export::ExportStructs;
+use serde::Serialize;
+
+// written by hand so a file without events still has it, same order as Row
+const HEADER: &[&str] = &[
+	"type", "time", "video_id", "title", "channel", "release_timestamp", "timestamp", "video_offset_ms", "removed", "removed_reason", "username", "channel_id",
+	"amount", "currency", "amount_value", "converted_value", "base_currency", "message", "months", "number", "sender",
+	"sticker_description", "sticker_image_url", "thumbnail_url", "header_color", "body_color", "id", "badges",
+];
+
+#[derive(Serialize, Debug, Default)]
+struct Row<'a> {
+	#[serde(rename = "type")]
+	json_type: &'a str,
+	time: &'a str,
+	video_id: Option<&'a str>,
+	title: Option<&'a str>,
+	channel: Option<&'a str>,
+	release_timestamp: Option<i64>,
+	timestamp: Option<&'a str>,
+	video_offset_ms: Option<i64>,
+	removed: bool,
+	removed_reason: Option<&'a str>,
+	username: &'a str,
+	channel_id: &'a str,
+	amount: Option<&'a str>,
+	currency: Option<&'a str>,
+	amount_value: Option<f64>,
+	converted_value: Option<f64>,
+	base_currency: Option<&'a str>,
+	message: Option<&'a str>,
+	months: Option<&'a str>,
+	number: Option<&'a str>,
+	sender: Option<&'a str>,
+	sticker_description: Option<&'a str>,
+	sticker_image_url: Option<&'a str>,
+	thumbnail_url: Option<&'a str>,
+	header_color: Option<i64>,
+	body_color: Option<i64>,
+	id: &'a str,
+	badges: Option<String>,
+}
+
+impl<'a> From<&'a ExportStructs> for Row<'a> {
+	fn from(event: &'a ExportStructs) -> Row<'a> {
+		match event {
+			ExportStructs::Donation(e) => Row {
+				json_type: "Donation",
+				time: &e.time,
+				video_id: e.video_id.as_deref(),
+				title: e.title.as_deref(),
+				channel: e.channel.as_deref(),
+				release_timestamp: e.release_timestamp,
+				timestamp: e.timestamp.as_deref(),
+				video_offset_ms: e.video_offset_ms,
+				removed: e.removed,
+				removed_reason: e.removed_reason.as_deref(),
+				username: &e.username,
+				channel_id: &e.channel_id,
+				id: &e.id,
+				amount: Some(&e.amount),
+				currency: e.currency.as_deref(),
+				amount_value: e.amount_value,
+				converted_value: e.converted_value,
+				base_currency: e.base_currency.as_deref(),
+				message: e.message.as_deref(),
+				thumbnail_url: Some(&e.thumbnail_url),
+				header_color: Some(e.header_color),
+				body_color: Some(e.body_color),
+				..Default::default()
+			},
+			ExportStructs::Membership(e) => Row {
+				json_type: "Membership",
+				time: &e.time,
+				video_id: e.video_id.as_deref(),
+				title: e.title.as_deref(),
+				channel: e.channel.as_deref(),
+				release_timestamp: e.release_timestamp,
+				timestamp: e.timestamp.as_deref(),
+				video_offset_ms: e.video_offset_ms,
+				removed: e.removed,
+				removed_reason: e.removed_reason.as_deref(),
+				username: &e.username,
+				channel_id: &e.channel_id,
+				id: &e.id,
+				message: e.message.as_deref(),
+				months: Some(&e.months),
+				thumbnail_url: Some(&e.thumbnail_url),
+				header_color: Some(e.header_color),
+				body_color: Some(e.body_color),
+				..Default::default()
+			},
+			ExportStructs::GiftMembership(e) => Row {
+				json_type: "GiftMembership",
+				time: &e.time,
+				video_id: e.video_id.as_deref(),
+				title: e.title.as_deref(),
+				channel: e.channel.as_deref(),
+				release_timestamp: e.release_timestamp,
+				timestamp: e.timestamp.as_deref(),
+				video_offset_ms: e.video_offset_ms,
+				removed: e.removed,
+				removed_reason: e.removed_reason.as_deref(),
+				username: &e.username,
+				channel_id: &e.channel_id,
+				id: &e.id,
+				sender: Some(&e.sender),
+				thumbnail_url: Some(&e.thumbnail_url),
+				header_color: Some(e.header_color),
+				body_color: Some(e.body_color),
+				..Default::default()
+			},
+			ExportStructs::GiftingMembership(e) => Row {
+				json_type: "GiftingMembership",
+				time: &e.time,
+				video_id: e.video_id.as_deref(),
+				title: e.title.as_deref(),
+				channel: e.channel.as_deref(),
+				release_timestamp: e.release_timestamp,
+				timestamp: e.timestamp.as_deref(),
+				video_offset_ms: e.video_offset_ms,
+				removed: e.removed,
+				removed_reason: e.removed_reason.as_deref(),
+				username: &e.username,
+				channel_id: &e.channel_id,
+				id: &e.id,
+				number: Some(&e.number),
+				thumbnail_url: Some(&e.thumbnail_url),
+				header_color: Some(e.header_color),
+				body_color: Some(e.body_color),
+				..Default::default()
+			},
+			ExportStructs::Sticker(e) => Row {
+				json_type: "Sticker",
+				time: &e.time,
+				video_id: e.video_id.as_deref(),
+				title: e.title.as_deref(),
+				channel: e.channel.as_deref(),
+				release_timestamp: e.release_timestamp,
+				timestamp: e.timestamp.as_deref(),
+				video_offset_ms: e.video_offset_ms,
+				removed: e.removed,
+				removed_reason: e.removed_reason.as_deref(),
+				username: &e.username,
+				channel_id: &e.channel_id,
+				id: &e.id,
+				amount: Some(&e.sticker_cost),
+				currency: e.currency.as_deref(),
+				amount_value: e.amount_value,
+				converted_value: e.converted_value,
+				base_currency: e.base_currency.as_deref(),
+				sticker_description: Some(&e.sticker_description),
+				sticker_image_url: Some(&e.sticker_image_url),
+				thumbnail_url: Some(&e.thumbnail_url),
+				header_color: Some(e.header_color),
+				body_color: Some(e.body_color),
+				..Default::default()
+			},
+			ExportStructs::TextMessage(e) => Row {
+				json_type: "TextMessage",
+				time: &e.time,
+				video_id: e.video_id.as_deref(),
+				title: e.title.as_deref(),
+				channel: e.channel.as_deref(),
+				release_timestamp: e.release_timestamp,
+				timestamp: e.timestamp.as_deref(),
+				video_offset_ms: e.video_offset_ms,
+				removed: e.removed,
+				removed_reason: e.removed_reason.as_deref(),
+				username: &e.username,
+				channel_id: &e.channel_id,
+				id: &e.id,
+				message: Some(&e.message),
+				badges: Some(e.badges.join(", ")),
+				..Default::default()
+			},
+		}
+	}
+}
+
+pub fn write(events: &[ExportStructs], path: &str, delimiter: u8) -> std::io::Result<()> {
+	let file = std::fs::File::create(path)?;
+	write_to(events, file, delimiter)
+}
+
+fn write_to(events: &[ExportStructs], output: impl std::io::Write, delimiter: u8) -> std::io::Result<()> {
+	let mut writer = csv::WriterBuilder::new().delimiter(delimiter).has_headers(false).from_writer(output);
+	writer.write_record(HEADER)?;
+	for event in events {
+		writer.serialize(Row::from(event))?;
+	}
+	writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use superchat_extractor::export;
+
+	fn write_string(events: &[ExportStructs], delimiter: u8) -> String {
+		let mut output = Vec::new();
+		write_to(events, &mut output, delimiter).unwrap();
+		String::from_utf8(output).unwrap()
+	}
+
+	#[test]
+	fn writes_the_header_without_events() {
+		assert_eq!(write_string(&[], b','), format!("{}\n", HEADER.join(",")));
+	}
+
+	#[test]
+	fn header_matches_the_row_fields() {
+		let mut writer = csv::Writer::from_writer(Vec::new());
+		writer.serialize(Row::default()).unwrap();
+		let written = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+		assert_eq!(written.lines().next().unwrap(), HEADER.join(","));
+	}
+
+	#[test]
+	fn quotes_commas_newlines_and_keeps_emoji() {
+		let message = export::parse_line(r#"{"type":"TextMessage","id":"m1","username":"Ann, the \"best\"","channel_id":"UCa","time":"0:05","badges":["Moderator","Member (6 months)"],"message":"hi 👋\nsecond line\tand a tab"}"#).unwrap();
+		let events = [message];
+
+		let csv = write_string(&events, b',');
+		let mut reader = csv::Reader::from_reader(csv.as_bytes());
+		let row = reader.records().next().unwrap().unwrap();
+		let field = |name: &str| row.get(HEADER.iter().position(|header| *header == name).unwrap()).unwrap().to_string();
+		assert_eq!(field("username"), "Ann, the \"best\"");
+		assert_eq!(field("message"), "hi 👋\nsecond line\tand a tab");
+		assert_eq!(field("badges"), "Moderator, Member (6 months)");
+		assert!(csv.contains("\"Ann, the \"\"best\"\"\""), "{csv}");
+
+		let tsv = write_string(&events, b'\t');
+		let mut reader = csv::ReaderBuilder::new().delimiter(b'\t').from_reader(tsv.as_bytes());
+		let row = reader.records().next().unwrap().unwrap();
+		assert_eq!(row.len(), HEADER.len());
+		assert_eq!(row.get(HEADER.iter().position(|header| *header == "message").unwrap()), Some("hi 👋\nsecond line\tand a tab"));
+	}
+}
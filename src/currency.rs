@@ -0,0 +1,238 @@
+// parsing of purchaseAmountText like "$5.00", "¥1,000", "CA$10.00", "1.234,56 €" or "CHF 5.00"
+// amounts are kept in the currency's smallest unit so adding them up is exact
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A purchase amount with the currency worked out.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Amount {
+	/// Iso 4217 code like "USD".
+	pub currency: String,
+	/// The amount in the currency's smallest unit, 500 for "$5.00", 1000 for "¥1,000" and 1500 for "KWD 1.500".
+	pub minor_units: i64,
+}
+
+impl Amount {
+	/// Rounds a value in whole units, like 5.0 for five dollars, to the currency's smallest unit.
+	pub fn from_value(currency: &str, value: f64) -> Amount {
+		let scale = 10f64.powi(exponent(currency) as i32);
+		Amount { currency: currency.to_string(), minor_units: (value * scale).round() as i64 }
+	}
+
+	/// The amount in whole units, 5.0 for "$5.00".
+	pub fn value(&self) -> f64 {
+		self.minor_units as f64 / 10f64.powi(exponent(&self.currency) as i32)
+	}
+}
+
+/// Shows the amount with the currency's digits after the decimal point, like "5.00 USD" or "1000 JPY".
+impl fmt::Display for Amount {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let exponent = exponent(&self.currency);
+		let scale = 10i64.pow(exponent);
+		let sign = if self.minor_units < 0 { "-" } else { "" };
+		let units = self.minor_units.unsigned_abs();
+		if exponent == 0 {
+			return write!(f, "{sign}{units} {}", self.currency);
+		}
+		write!(f, "{sign}{}.{:0width$} {}", units / scale as u64, units % scale as u64, self.currency, width = exponent as usize)
+	}
+}
+
+// symbols youtube puts in front of or behind the number
+const SYMBOLS: &[(&str, &str)] = &[
+	("US$", "USD"),
+	("CA$", "CAD"),
+	("AU$", "AUD"),
+	("A$", "AUD"),
+	("NZ$", "NZD"),
+	("HK$", "HKD"),
+	("NT$", "TWD"),
+	("MX$", "MXN"),
+	("R$", "BRL"),
+	("S$", "SGD"),
+	("RD$", "DOP"),
+	("C$", "NIO"),
+	("CN¥", "CNY"),
+	("JP¥", "JPY"),
+	("E£", "EGP"),
+	("S/", "PEN"),
+	("$", "USD"),
+	("€", "EUR"),
+	("£", "GBP"),
+	("¥", "JPY"),
+	("₩", "KRW"),
+	("₹", "INR"),
+	("₱", "PHP"),
+	("₪", "ILS"),
+	("₫", "VND"),
+	("₺", "TRY"),
+	("₽", "RUB"),
+	("₴", "UAH"),
+	("₸", "KZT"),
+	("₦", "NGN"),
+	("₾", "GEL"),
+	("₡", "CRC"),
+	("₲", "PYG"),
+	("฿", "THB"),
+	("zł", "PLN"),
+	("Kč", "CZK"),
+	("Ft", "HUF"),
+	("лв.", "BGN"),
+	("лв", "BGN"),
+	("RM", "MYR"),
+	("Rp", "IDR"),
+	("Rs", "PKR"),
+	("Bs", "BOB"),
+];
+
+// currencies with three digits after the decimal point
+// used to tell "KWD 1.500" apart from "¥1,500"
+const THREE_DECIMALS: &[&str] = &["BHD", "IQD", "JOD", "KWD", "LYD", "OMR", "TND"];
+
+// currencies without a minor unit
+const NO_DECIMALS: &[&str] = &[
+	"BIF", "CLP", "DJF", "GNF", "ISK", "JPY", "KMF", "KRW", "PYG", "RWF", "UGX", "UYI", "VND", "VUV", "XAF", "XOF", "XPF",
+];
+
+/// How many digits the currency has after the decimal point, 2 unless iso 4217 says otherwise.
+pub fn exponent(currency: &str) -> u32 {
+	if NO_DECIMALS.contains(&currency) {
+		0
+	} else if THREE_DECIMALS.contains(&currency) {
+		3
+	} else {
+		2
+	}
+}
+
+/// Reads youtube's purchaseAmountText, `None` when the currency or number isn't recognized.
+pub fn parse_amount(text: &str) -> Option<Amount> {
+	// youtube uses non breaking spaces between the code and the number in some locales
+	let text = text.trim_matches(|c: char| c.is_whitespace());
+	let number_start = text.find(|c: char| c.is_ascii_digit())?;
+	let number_end = text.rfind(|c: char| c.is_ascii_digit())? + 1;
+
+	let prefix = text[..number_start].trim_matches(|c: char| c.is_whitespace());
+	let suffix = text[number_end..].trim_matches(|c: char| c.is_whitespace());
+	let currency = match (prefix.is_empty(), suffix.is_empty()) {
+		(false, true) => currency_code(prefix)?,
+		(true, false) => currency_code(suffix)?,
+		_ => return None,
+	};
+
+	let minor_units = parse_number(&text[number_start..number_end], exponent(&currency))?;
+	Some(Amount { currency, minor_units })
+}
+
+fn currency_code(symbol: &str) -> Option<String> {
+	// things like "CHF", "SEK" or "PLN" are already the iso code
+	if symbol.len() == 3 && symbol.chars().all(|c| c.is_ascii_uppercase()) {
+		return Some(symbol.to_string());
+	}
+	SYMBOLS
+		.iter()
+		.find(|(known, _)| *known == symbol)
+		.map(|(_, code)| code.to_string())
+}
+
+// handles "1,234.56", "1.234,56", "1 234,56", "1'234.56", "1,00,000.00" and "1,000"
+// gives the number in minor units, None if it has more decimals than the currency
+fn parse_number(number: &str, exponent: u32) -> Option<i64> {
+	// only the last separator can be the decimal point
+	// if it's followed by exactly three digits it's grouping unless the currency uses three decimals
+	let decimal_point = number.char_indices().rev().find(|(_, c)| !c.is_ascii_digit()).and_then(|(position, separator)| {
+		let digits_after = number.len() - position - separator.len_utf8();
+		let is_decimal = matches!(separator, '.' | ',') && (digits_after != 3 || exponent == 3);
+		is_decimal.then_some(position)
+	});
+
+	let mut units = String::with_capacity(number.len());
+	let mut decimals = String::new();
+	for (position, c) in number.char_indices() {
+		if !c.is_ascii_digit() {
+			continue;
+		}
+		if decimal_point.is_some_and(|decimal_point| position > decimal_point) {
+			decimals.push(c);
+		} else {
+			units.push(c);
+		}
+	}
+
+	// "$5.5" is 550 cents, "¥1000.00" is still 1000 yen but "¥1000.50" can't be right
+	let exponent = exponent as usize;
+	if decimals.len() > exponent {
+		if decimals[exponent..].chars().any(|c| c != '0') {
+			return None;
+		}
+		decimals.truncate(exponent);
+	}
+	while decimals.len() < exponent {
+		decimals.push('0');
+	}
+	format!("{units}{decimals}").parse::<i64>().ok()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_symbols_and_digit_grouping() {
+		let cases = [
+			("$5.00", "USD", 500),
+			("¥1,000", "JPY", 1000),
+			("CA$10.00", "CAD", 1000),
+			("₱100.00", "PHP", 10000),
+			("1.234,56 €", "EUR", 123456),
+			("₹1,00,000.00", "INR", 10000000),
+			("1 000,00 ₽", "RUB", 100000),
+			("1\u{a0}000,00\u{a0}₽", "RUB", 100000),
+			("KWD 1.500", "KWD", 1500),
+			("CHF 5.00", "CHF", 500),
+			("5,00 zł", "PLN", 500),
+			("£2.5", "GBP", 250),
+			("€20", "EUR", 2000),
+			("₩50,000", "KRW", 50000),
+			("NT$75.00", "TWD", 7500),
+			("R$ 1.000,00", "BRL", 100000),
+			("CHF 1'234.50", "CHF", 123450),
+			// three digits after the only separator is grouping unless the currency has three decimals
+			("€1.500", "EUR", 150000),
+			("BHD 0.500", "BHD", 500),
+			("¥1000.00", "JPY", 1000),
+		];
+		for (text, currency, minor_units) in cases {
+			assert_eq!(parse_amount(text), Some(Amount { currency: currency.to_string(), minor_units }), "{text}");
+		}
+	}
+
+	#[test]
+	fn rejects_what_it_cant_read() {
+		for text in ["", "free", "$", "5.00", "XX$5.00", "$5 €", "¥1000.50", "five dollars"] {
+			assert_eq!(parse_amount(text), None, "{text}");
+		}
+	}
+
+	#[test]
+	fn every_symbol_maps_to_an_iso_code() {
+		for (symbol, code) in SYMBOLS {
+			let amount = parse_amount(&format!("{symbol}12.00")).unwrap_or_else(|| panic!("{symbol}"));
+			assert_eq!(amount.currency, *code);
+			assert_eq!(amount.value(), 12.0);
+		}
+	}
+
+	#[test]
+	fn adds_up_exactly_and_shows_the_currency_decimals() {
+		let total: i64 = ["$1.10", "$2.20", "$0.70"].iter().map(|text| parse_amount(text).unwrap().minor_units).sum();
+		assert_eq!(total, 400);
+		assert_eq!(Amount { currency: "USD".to_string(), minor_units: total }.to_string(), "4.00 USD");
+		assert_eq!(Amount { currency: "JPY".to_string(), minor_units: 1000 }.to_string(), "1000 JPY");
+		assert_eq!(Amount { currency: "KWD".to_string(), minor_units: -1005 }.to_string(), "-1.005 KWD");
+		assert_eq!(Amount::from_value("USD", 12.199999999), Amount { currency: "USD".to_string(), minor_units: 1220 });
+		assert_eq!(Amount::from_value("JPY", 1000.4).value(), 1000.0);
+	}
+}
@@ -0,0 +1,114 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+// keeps track of payloads youtube sends that we don't know how to parse yet
+// grouped by where they showed up and their top level key
+#[derive(Default)]
+pub struct DriftReport {
+	entries: BTreeMap<(&'static str, String), DriftEntry>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DriftEntry {
+	pub kind: &'static str,
+	pub key: String,
+	pub count: usize,
+	pub first_seen_line: usize,
+	// the first payload seen with this key
+	pub sample: serde_json::Value,
+}
+
+impl DriftReport {
+	// returns the key the payload was grouped under
+	pub fn record(&mut self, kind: &'static str, value: serde_json::Value, line_number: usize) -> String {
+		let key = top_level_key(&value);
+		let entry = self.entries.entry((kind, key.clone())).or_insert_with(|| DriftEntry {
+			kind,
+			key: key.clone(),
+			count: 0,
+			first_seen_line: line_number,
+			sample: value,
+		});
+		entry.count += 1;
+		key
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	pub fn entries(&self) -> impl Iterator<Item = &DriftEntry> {
+		self.entries.values()
+	}
+
+	// one json object per line, one line per kind and key
+	pub fn write_jsonl(&self, path: &str) -> std::io::Result<()> {
+		let file = std::fs::File::create(path)?;
+		let mut file = std::io::BufWriter::new(file);
+		for entry in self.entries() {
+			let json = serde_json::to_string(entry).expect("could not serialize drift entry");
+			writeln!(file, "{json}")?;
+		}
+		file.flush()
+	}
+}
+
+// actions carry clickTrackingParams next to the real payload so skip it
+fn top_level_key(value: &serde_json::Value) -> String {
+	match value {
+		serde_json::Value::Object(map) => map
+			.keys()
+			.find(|key| key.as_str() != "clickTrackingParams")
+			.cloned()
+			.unwrap_or_else(|| "<empty object>".to_string()),
+		_ => "<not an object>".to_string(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+
+	#[test]
+	fn groups_by_kind_and_top_level_key() {
+		let mut drift = DriftReport::default();
+		assert_eq!(drift.record("action", json!({"clickTrackingParams": "x", "newAction": {"n": 1}}), 3), "newAction");
+		drift.record("action", json!({"newAction": {"n": 2}, "clickTrackingParams": "y"}), 9);
+		drift.record("chat_item", json!({"newAction": {}}), 4);
+		drift.record("chat_item", json!({"liveChatNewRenderer": {}}), 5);
+		assert_eq!(drift.record("action", json!({"clickTrackingParams": "z"}), 6), "<empty object>");
+		assert_eq!(drift.record("action", json!([1, 2]), 7), "<not an object>");
+
+		let entries: Vec<&DriftEntry> = drift.entries().collect();
+		let keys: Vec<(&str, &str, usize)> = entries.iter().map(|entry| (entry.kind, entry.key.as_str(), entry.count)).collect();
+		assert_eq!(keys, vec![
+			("action", "<empty object>", 1),
+			("action", "<not an object>", 1),
+			("action", "newAction", 2),
+			("chat_item", "liveChatNewRenderer", 1),
+			("chat_item", "newAction", 1),
+		]);
+		// the first payload and line are kept
+		assert_eq!(entries[2].first_seen_line, 3);
+		assert_eq!(entries[2].sample["newAction"]["n"], 1);
+	}
+
+	#[test]
+	fn writes_one_line_per_group() {
+		let mut drift = DriftReport::default();
+		drift.record("action", json!({"newAction": {}}), 1);
+		drift.record("action", json!({"newAction": {}}), 2);
+		drift.record("chat_item", json!({"liveChatNewRenderer": {}}), 2);
+		let path = std::env::temp_dir().join(format!("superchat-extractor-drift-{}.jsonl", std::process::id()));
+		drift.write_jsonl(path.to_str().unwrap()).unwrap();
+		let written = std::fs::read_to_string(&path).unwrap();
+		std::fs::remove_file(&path).unwrap();
+		let lines: Vec<serde_json::Value> = written.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+		assert_eq!(lines.len(), 2);
+		assert_eq!(lines[0]["key"], "newAction");
+		assert_eq!(lines[0]["count"], 2);
+		assert_eq!(lines[1]["kind"], "chat_item");
+	}
+}
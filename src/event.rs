@@ -0,0 +1,182 @@
+// the typed events the library hands out, one per thing that happened in the chat
+// youtube's own structs stay in youtube.rs so changes there don't break users of these
+
+use crate::currency::Amount;
+use serde::Serialize;
+
+/// Something that happened in the chat.
+///
+/// A single line of a yt-dlp `.live_chat.json` file can hold several of these.
+/// Actions that don't carry anything useful, like banners and ticker items, are skipped.
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum ChatEvent {
+	/// A paid message.
+	Superchat(Superchat),
+	/// A paid sticker.
+	Sticker(Sticker),
+	/// Someone joined the channel membership or announced a membership milestone.
+	Membership(Membership),
+	/// Someone bought memberships for other viewers.
+	GiftPurchase(GiftPurchase),
+	/// Someone received a membership bought by another viewer.
+	GiftRedemption(GiftRedemption),
+	/// A normal chat message.
+	TextMessage(TextMessage),
+	/// A moderator deleted one chat item, which can be a message or a paid event.
+	MessageDeleted(MessageDeleted),
+	/// Every message of a channel was removed, which happens when they are banned or timed out.
+	AuthorRemoved(AuthorRemoved),
+	/// A poll was created or its votes changed, only the latest state matters.
+	PollUpdate(Poll),
+	/// A payload none of the known youtube structures matched.
+	Unknown(Unknown),
+}
+
+/// When an event happened.
+#[derive(Serialize, Debug, Clone)]
+pub struct EventTime {
+	/// Wall clock time in microseconds since the unix epoch.
+	pub timestamp_usec: i64,
+	/// Youtube's "1:23:45" into the stream, only present in replays.
+	pub timestamp_text: Option<String>,
+	/// Milliseconds into the video when yt-dlp recorded it, negative for chat from before the stream started.
+	pub video_offset_ms: Option<i64>,
+}
+
+/// Who sent an event.
+#[derive(Serialize, Debug, Clone)]
+pub struct Author {
+	/// The `UC...` id of their channel.
+	pub channel_id: String,
+	pub name: String,
+	/// The largest version of their avatar.
+	pub photo_url: Option<String>,
+	/// Tooltips of their badges, like "Moderator", "Verified" or "Member (6 months)".
+	pub badges: Vec<String>,
+}
+
+/// Chat text with custom emojis flattened to `:shortcut:` and standard emojis as the emoji itself.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct Message {
+	pub text: String,
+	/// Custom emojis that appear in the text.
+	pub emojis: Vec<CustomEmoji>,
+	/// Parts of the message in a format this library doesn't know yet.
+	pub unknown_runs: Vec<serde_json::Value>,
+}
+
+/// A channel's own emoji, shown as `:label:` in [`Message::text`].
+#[derive(Serialize, Debug, Clone)]
+pub struct CustomEmoji {
+	pub label: String,
+	pub image_url: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Superchat {
+	/// Youtube's id of the chat item, what [`MessageDeleted::target_id`] refers to.
+	pub id: String,
+	pub author: Author,
+	pub time: EventTime,
+	/// The amount as youtube shows it, like "$5.00" or "¥1,000".
+	pub amount_text: String,
+	/// The currency and value of `amount_text` when it could be recognized.
+	pub amount: Option<Amount>,
+	pub message: Option<Message>,
+	/// Colors as argb.
+	pub header_color: i64,
+	pub body_color: i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Sticker {
+	pub id: String,
+	pub author: Author,
+	pub time: EventTime,
+	pub amount_text: String,
+	pub amount: Option<Amount>,
+	/// What the sticker shows, youtube's accessibility label.
+	pub description: String,
+	pub image_url: Option<String>,
+	/// Color as argb.
+	pub background_color: i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Membership {
+	pub id: String,
+	pub author: Author,
+	pub time: EventTime,
+	/// Something like "Member for 6 months", `None` for a new member.
+	pub months: Option<Message>,
+	/// The message sent with a milestone.
+	pub message: Option<Message>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GiftPurchase {
+	pub id: String,
+	pub author: Author,
+	pub time: EventTime,
+	/// How many memberships were bought, as youtube wrote it.
+	pub number: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GiftRedemption {
+	pub id: String,
+	/// Who received the membership.
+	pub author: Author,
+	pub time: EventTime,
+	/// Youtube's announcement, like "received a gift membership by someone".
+	pub message: Message,
+	/// Name of whoever bought the membership.
+	pub sender: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TextMessage {
+	pub id: String,
+	pub author: Author,
+	pub time: EventTime,
+	pub message: Message,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MessageDeleted {
+	/// Id of the removed chat item.
+	pub target_id: String,
+	/// Milliseconds into the video when it was removed.
+	pub video_offset_ms: Option<i64>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct AuthorRemoved {
+	/// Channel whose messages were removed.
+	pub channel_id: String,
+	pub video_offset_ms: Option<i64>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Poll {
+	pub id: String,
+	pub question: Message,
+	/// Youtube's line under the question, like "Poll · 1,234 votes".
+	pub metadata: Message,
+	pub choices: Vec<PollChoice>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PollChoice {
+	pub text: Message,
+	/// Like "42%".
+	pub vote_percentage: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Unknown {
+	/// "action" for an unknown chat action, "chat_item" for an unknown thing added to the chat.
+	pub kind: &'static str,
+	pub value: serde_json::Value,
+}
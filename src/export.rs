@@ -0,0 +1,398 @@
+// the records superchat-extractor writes to --outputfile, sends over --live and keeps in memory while it runs
+// one struct per event type, the "type" field comes from the enum tag
+// files from older versions are missing fields, everything added later has a default so they still read
+
+use crate::currency;
+use serde::{Deserialize, Serialize};
+use std::io::BufRead;
+
+/// One exported event, serialized with its variant name in a `"type"` field.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum ExportStructs {
+	/// A superchat.
+	Donation(Donation),
+	Membership(Membership),
+	/// Someone receiving a gifted membership.
+	GiftMembership(Redemption),
+	/// Someone buying memberships for others.
+	GiftingMembership(Gift),
+	Sticker(Sticker),
+	/// A normal chat message, only exported with `--export-chat`.
+	TextMessage(ChatMessage),
+}
+
+/// A superchat.
+///
+/// The fields from `id` to `removed_reason` are the same on every event type.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Donation {
+	/// Youtube's id of the chat item, what a deletion targets. Empty in files from before ids were exported.
+	#[serde(default)]
+	pub id: String,
+	pub username: String,
+	pub channel_id: String,
+	/// As youtube shows it, like "$5.00".
+	pub amount: String,
+	/// Parsed from `amount`.
+	#[serde(default)]
+	pub currency: Option<String>,
+	#[serde(default)]
+	pub amount_value: Option<f64>,
+	/// `amount_value` converted with `--rates`.
+	#[serde(default)]
+	pub converted_value: Option<f64>,
+	#[serde(default)]
+	pub base_currency: Option<String>,
+	pub message: Option<String>,
+	/// Formatted with `--time-format`.
+	#[serde(default)]
+	pub time: String,
+	/// Youtube id of the stream, from the file name or the `.info.json` next to it.
+	#[serde(default)]
+	pub video_id: Option<String>,
+	/// Title of the stream, from the same place.
+	#[serde(default)]
+	pub title: Option<String>,
+	/// Name of the channel that streamed, only known from the `.info.json`.
+	#[serde(default)]
+	pub channel: Option<String>,
+	/// Unix seconds the stream started at, also from the `.info.json`.
+	#[serde(default)]
+	pub release_timestamp: Option<i64>,
+	/// When the event happened, rfc 3339 in utc.
+	#[serde(default)]
+	pub timestamp: Option<String>,
+	/// Milliseconds into the video.
+	#[serde(default)]
+	pub video_offset_ms: Option<i64>,
+	/// Whether moderators removed it, set once the whole chat was read.
+	#[serde(default)]
+	pub removed: bool,
+	/// "deleted" for a single removed message, "author_wiped" when all of the author's messages were removed.
+	#[serde(default)]
+	pub removed_reason: Option<String>,
+	pub header_color: i64,
+	pub body_color: i64,
+	pub thumbnail_url: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Membership {
+	#[serde(default)]
+	pub id: String,
+	pub username: String,
+	pub channel_id: String,
+	/// Like "Member for 6 months", or "New member.".
+	pub months: String,
+	pub message: Option<String>,
+	#[serde(default)]
+	pub time: String,
+	#[serde(default)]
+	pub video_id: Option<String>,
+	#[serde(default)]
+	pub title: Option<String>,
+	#[serde(default)]
+	pub channel: Option<String>,
+	#[serde(default)]
+	pub release_timestamp: Option<i64>,
+	#[serde(default)]
+	pub timestamp: Option<String>,
+	#[serde(default)]
+	pub video_offset_ms: Option<i64>,
+	#[serde(default)]
+	pub removed: bool,
+	#[serde(default)]
+	pub removed_reason: Option<String>,
+	pub header_color: i64,
+	pub body_color: i64,
+	pub thumbnail_url: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Redemption {
+	#[serde(default)]
+	pub id: String,
+	pub thumbnail_url: String,
+	pub username: String,
+	pub channel_id: String,
+	#[serde(default)]
+	pub time: String,
+	#[serde(default)]
+	pub video_id: Option<String>,
+	#[serde(default)]
+	pub title: Option<String>,
+	#[serde(default)]
+	pub channel: Option<String>,
+	#[serde(default)]
+	pub release_timestamp: Option<i64>,
+	#[serde(default)]
+	pub timestamp: Option<String>,
+	#[serde(default)]
+	pub video_offset_ms: Option<i64>,
+	#[serde(default)]
+	pub removed: bool,
+	#[serde(default)]
+	pub removed_reason: Option<String>,
+	/// Who bought the membership, "unknown" when youtube didn't say.
+	pub sender: String,
+	pub header_color: i64,
+	pub body_color: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Gift {
+	#[serde(default)]
+	pub id: String,
+	pub username: String,
+	pub channel_id: String,
+	#[serde(default)]
+	pub time: String,
+	#[serde(default)]
+	pub video_id: Option<String>,
+	#[serde(default)]
+	pub title: Option<String>,
+	#[serde(default)]
+	pub channel: Option<String>,
+	#[serde(default)]
+	pub release_timestamp: Option<i64>,
+	#[serde(default)]
+	pub timestamp: Option<String>,
+	#[serde(default)]
+	pub video_offset_ms: Option<i64>,
+	#[serde(default)]
+	pub removed: bool,
+	#[serde(default)]
+	pub removed_reason: Option<String>,
+	/// How many memberships were bought.
+	pub number: String,
+	pub header_color: i64,
+	pub body_color: i64,
+	pub thumbnail_url: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Sticker {
+	#[serde(default)]
+	pub id: String,
+	pub username: String,
+	pub channel_id: String,
+	pub sticker_cost: String,
+	/// Parsed from `sticker_cost`.
+	#[serde(default)]
+	pub currency: Option<String>,
+	#[serde(default)]
+	pub amount_value: Option<f64>,
+	#[serde(default)]
+	pub converted_value: Option<f64>,
+	#[serde(default)]
+	pub base_currency: Option<String>,
+	pub sticker_description: String,
+	#[serde(default)]
+	pub sticker_image_url: String,
+	#[serde(default)]
+	pub time: String,
+	#[serde(default)]
+	pub video_id: Option<String>,
+	#[serde(default)]
+	pub title: Option<String>,
+	#[serde(default)]
+	pub channel: Option<String>,
+	#[serde(default)]
+	pub release_timestamp: Option<i64>,
+	#[serde(default)]
+	pub timestamp: Option<String>,
+	#[serde(default)]
+	pub video_offset_ms: Option<i64>,
+	#[serde(default)]
+	pub removed: bool,
+	#[serde(default)]
+	pub removed_reason: Option<String>,
+	pub thumbnail_url: String,
+	pub header_color: i64,
+	pub body_color: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChatMessage {
+	pub id: String,
+	pub username: String,
+	pub channel_id: String,
+	pub time: String,
+	#[serde(default)]
+	pub video_id: Option<String>,
+	#[serde(default)]
+	pub title: Option<String>,
+	#[serde(default)]
+	pub channel: Option<String>,
+	#[serde(default)]
+	pub release_timestamp: Option<i64>,
+	#[serde(default)]
+	pub timestamp: Option<String>,
+	#[serde(default)]
+	pub video_offset_ms: Option<i64>,
+	#[serde(default)]
+	pub removed: bool,
+	#[serde(default)]
+	pub removed_reason: Option<String>,
+	/// Tooltips of the author's badges, like "Moderator".
+	pub badges: Vec<String>,
+	pub message: String,
+}
+
+impl ExportStructs {
+	pub fn id(&self) -> &str {
+		match self {
+			ExportStructs::Donation(e) => &e.id,
+			ExportStructs::Membership(e) => &e.id,
+			ExportStructs::GiftMembership(e) => &e.id,
+			ExportStructs::GiftingMembership(e) => &e.id,
+			ExportStructs::Sticker(e) => &e.id,
+			ExportStructs::TextMessage(e) => &e.id,
+		}
+	}
+
+	pub fn username(&self) -> &str {
+		match self {
+			ExportStructs::Donation(e) => &e.username,
+			ExportStructs::Membership(e) => &e.username,
+			ExportStructs::GiftMembership(e) => &e.username,
+			ExportStructs::GiftingMembership(e) => &e.username,
+			ExportStructs::Sticker(e) => &e.username,
+			ExportStructs::TextMessage(e) => &e.username,
+		}
+	}
+
+	pub fn channel_id(&self) -> &str {
+		match self {
+			ExportStructs::Donation(e) => &e.channel_id,
+			ExportStructs::Membership(e) => &e.channel_id,
+			ExportStructs::GiftMembership(e) => &e.channel_id,
+			ExportStructs::GiftingMembership(e) => &e.channel_id,
+			ExportStructs::Sticker(e) => &e.channel_id,
+			ExportStructs::TextMessage(e) => &e.channel_id,
+		}
+	}
+
+	pub fn time(&self) -> &str {
+		match self {
+			ExportStructs::Donation(e) => &e.time,
+			ExportStructs::Membership(e) => &e.time,
+			ExportStructs::GiftMembership(e) => &e.time,
+			ExportStructs::GiftingMembership(e) => &e.time,
+			ExportStructs::Sticker(e) => &e.time,
+			ExportStructs::TextMessage(e) => &e.time,
+		}
+	}
+
+	pub fn video_id(&self) -> Option<&str> {
+		match self {
+			ExportStructs::Donation(e) => e.video_id.as_deref(),
+			ExportStructs::Membership(e) => e.video_id.as_deref(),
+			ExportStructs::GiftMembership(e) => e.video_id.as_deref(),
+			ExportStructs::GiftingMembership(e) => e.video_id.as_deref(),
+			ExportStructs::Sticker(e) => e.video_id.as_deref(),
+			ExportStructs::TextMessage(e) => e.video_id.as_deref(),
+		}
+	}
+
+	pub fn title(&self) -> Option<&str> {
+		match self {
+			ExportStructs::Donation(e) => e.title.as_deref(),
+			ExportStructs::Membership(e) => e.title.as_deref(),
+			ExportStructs::GiftMembership(e) => e.title.as_deref(),
+			ExportStructs::GiftingMembership(e) => e.title.as_deref(),
+			ExportStructs::Sticker(e) => e.title.as_deref(),
+			ExportStructs::TextMessage(e) => e.title.as_deref(),
+		}
+	}
+
+	/// Sets `removed` and why.
+	pub fn mark_removed(&mut self, reason: &str) {
+		let (removed, removed_reason) = match self {
+			ExportStructs::Donation(e) => (&mut e.removed, &mut e.removed_reason),
+			ExportStructs::Membership(e) => (&mut e.removed, &mut e.removed_reason),
+			ExportStructs::GiftMembership(e) => (&mut e.removed, &mut e.removed_reason),
+			ExportStructs::GiftingMembership(e) => (&mut e.removed, &mut e.removed_reason),
+			ExportStructs::Sticker(e) => (&mut e.removed, &mut e.removed_reason),
+			ExportStructs::TextMessage(e) => (&mut e.removed, &mut e.removed_reason),
+		};
+		*removed = true;
+		*removed_reason = Some(reason.to_string());
+	}
+}
+
+/// Reads one line of an output file, from this version or an older one.
+///
+/// Amounts in files written before currencies were parsed get parsed here.
+pub fn parse_line(line: &str) -> Result<ExportStructs, serde_json::Error> {
+	let mut event: ExportStructs = serde_json::from_str(line)?;
+	let (amount, currency, amount_value) = match &mut event {
+		ExportStructs::Donation(e) => (&e.amount, &mut e.currency, &mut e.amount_value),
+		ExportStructs::Sticker(e) => (&e.sticker_cost, &mut e.currency, &mut e.amount_value),
+		_ => return Ok(event),
+	};
+	if currency.is_none() && amount_value.is_none() {
+		if let Some(parsed) = currency::parse_amount(amount) {
+			*amount_value = Some(parsed.value());
+			*currency = Some(parsed.currency);
+		}
+	}
+	Ok(event)
+}
+
+/// Reads a whole jsonl output file, skipping empty lines.
+pub fn read<R: BufRead>(reader: R) -> Result<Vec<ExportStructs>, crate::Error> {
+	let mut events = Vec::new();
+	for line in reader.lines() {
+		let line = line?;
+		if line.trim().is_empty() {
+			continue;
+		}
+		events.push(parse_line(&line)?);
+	}
+	Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn reads_the_first_output_format() {
+		// no ids, times, parsed amounts or sticker images yet
+		let old = r#"{"type":"GiftingMembership","username":"Samuel","channel_id":"UCa","number":"10","header_color":688195,"body_color":1023320,"thumbnail_url":"https://yt/a"}
+{"type":"Sticker","username":"Bob","channel_id":"UCb","sticker_cost":"¥1,000","sticker_description":"cat","thumbnail_url":"https://yt/b","header_color":1,"body_color":1}
+
+{"type":"Donation","username":"Chiyo","channel_id":"UCc","amount":"$5.00","message":null,"time":"1:02","header_color":1,"body_color":1,"thumbnail_url":"https://yt/c"}
+"#;
+		let events = read(old.as_bytes()).unwrap();
+		assert_eq!(events.len(), 3);
+		assert!(matches!(&events[0], ExportStructs::GiftingMembership(e) if e.number == "10" && e.time.is_empty() && e.id.is_empty()));
+		let ExportStructs::Sticker(sticker) = &events[1] else { panic!("not a sticker: {:?}", events[1]) };
+		assert_eq!(sticker.currency.as_deref(), Some("JPY"));
+		assert_eq!(sticker.amount_value, Some(1000.0));
+		let ExportStructs::Donation(donation) = &events[2] else { panic!("not a donation: {:?}", events[2]) };
+		assert_eq!(donation.currency.as_deref(), Some("USD"));
+		assert!(!donation.removed);
+	}
+
+	#[test]
+	fn reads_the_example_output() {
+		let events = read(include_str!("../output.txt").as_bytes()).unwrap();
+		assert!(!events.is_empty());
+	}
+
+	#[test]
+	fn round_trips_with_type_tag() {
+		let line = r#"{"type":"TextMessage","id":"m1","username":"Alice","channel_id":"UCa","time":"0:05","badges":[],"message":"hi"}"#;
+		let mut event = parse_line(line).unwrap();
+		event.mark_removed("deleted");
+		let json: serde_json::Value = serde_json::to_value(&event).unwrap();
+		assert_eq!(json["type"], "TextMessage");
+		assert_eq!(json["removed"], true);
+		assert_eq!(json["removed_reason"], "deleted");
+		assert_eq!(event.id(), "m1");
+		assert_eq!(event.channel_id(), "UCa");
+	}
+}
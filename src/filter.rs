@@ -0,0 +1,338 @@
+// --type, --min-amount, --author, --channel, --since, --until and --message-regex
+// an event has to pass every filter that was given, an event a filter can't check doesn't pass it
+// so --min-amount drops memberships and --message-regex drops stickers
+
+use crate::rates::{self, RateTable};
+use crate::timestamps;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use clap::ValueEnum;
+use regex::Regex;
+use superchat_extractor::currency::{self, Amount};
+use superchat_extractor::event::{Author, EventTime};
+use superchat_extractor::ChatEvent;
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum EventType {
+	Superchat,
+	Sticker,
+	Membership,
+	// someone buying memberships for others
+	Gift,
+	// someone receiving a gifted membership
+	Redemption,
+	Message,
+	Poll,
+	// deleted messages and author wipes
+	Removal,
+}
+
+// a point in time or a point in the video
+#[derive(Clone, Copy, Debug)]
+pub enum TimeBound {
+	Absolute(DateTime<Utc>),
+	Offset(i64),
+}
+
+// "2023-05-13T17:46:45Z", "2023-05-13T17:46:45+09:00", "2023-05-13 17:46:45" or "2023-05-13" in utc,
+// or an offset into the video like "1:23:45", "5:00" or "-0:30"
+pub fn parse_time_bound(text: &str) -> Result<TimeBound, String> {
+	if let Some(offset) = parse_offset(text) {
+		return Ok(TimeBound::Offset(offset));
+	}
+	if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+		return Ok(TimeBound::Absolute(time.with_timezone(&Utc)));
+	}
+	for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"] {
+		if let Ok(time) = NaiveDateTime::parse_from_str(text, format) {
+			return Ok(TimeBound::Absolute(time.and_utc()));
+		}
+	}
+	if let Some(time) = NaiveDate::parse_from_str(text, "%Y-%m-%d").ok().and_then(|date| date.and_hms_opt(0, 0, 0)) {
+		return Ok(TimeBound::Absolute(time.and_utc()));
+	}
+	Err(format!("{text} is not a date like 2023-05-13T17:46:45Z or an offset into the video like 1:23:45"))
+}
+
+// the reverse of timestamps::format_offset, in milliseconds
+fn parse_offset(text: &str) -> Option<i64> {
+	let (sign, rest) = match text.strip_prefix('-') {
+		Some(rest) => (-1, rest),
+		None => (1, text),
+	};
+	let parts: Vec<&str> = rest.split(':').collect();
+	if !(2..=3).contains(&parts.len()) || parts.iter().any(|part| part.is_empty() || !part.chars().all(|c| c.is_ascii_digit())) {
+		return None;
+	}
+	let seconds = parts.iter().try_fold(0i64, |total, part| Some(total * 60 + part.parse::<i64>().ok()?))?;
+	Some(sign * seconds * 1000)
+}
+
+// "$20", "20 USD", "¥1,000" or a bare "20" in --base-currency
+#[derive(Clone, Debug)]
+pub struct MinAmount {
+	pub currency: Option<String>,
+	pub value: f64,
+}
+
+pub fn parse_min_amount(text: &str) -> Result<MinAmount, String> {
+	if let Ok(value) = text.trim().parse::<f64>() {
+		return Ok(MinAmount { currency: None, value });
+	}
+	currency::parse_amount(text)
+		.map(|amount| MinAmount { value: amount.value(), currency: Some(amount.currency) })
+		.ok_or_else(|| format!("{text} is not an amount like $20, 20 USD or 20"))
+}
+
+#[derive(Default)]
+pub struct Filter {
+	// empty means every type
+	pub types: Vec<EventType>,
+	pub min_amount: Option<MinAmount>,
+	// what a bare --min-amount is in
+	pub base_currency: String,
+	// usernames, compared without case
+	pub authors: Vec<String>,
+	pub channels: Vec<String>,
+	pub since: Option<TimeBound>,
+	pub until: Option<TimeBound>,
+	pub message_regex: Option<Regex>,
+}
+
+impl Filter {
+	// rate_table lets --min-amount compare different currencies,
+	// release_timestamp works out video offsets for lines that don't have one
+	pub fn matches(&self, event: &ChatEvent, rate_table: Option<&RateTable>, release_timestamp: Option<i64>) -> bool {
+		let (event_type, author, time, amount, message) = match event {
+			ChatEvent::Superchat(e) => (EventType::Superchat, Some(&e.author), Some(&e.time), e.amount.as_ref(), e.message.as_ref().map(|m| m.text.as_str())),
+			ChatEvent::Sticker(e) => (EventType::Sticker, Some(&e.author), Some(&e.time), e.amount.as_ref(), None),
+			ChatEvent::Membership(e) => (EventType::Membership, Some(&e.author), Some(&e.time), None, e.message.as_ref().map(|m| m.text.as_str())),
+			ChatEvent::GiftPurchase(e) => (EventType::Gift, Some(&e.author), Some(&e.time), None, None),
+			ChatEvent::GiftRedemption(e) => (EventType::Redemption, Some(&e.author), Some(&e.time), None, None),
+			ChatEvent::TextMessage(e) => (EventType::Message, Some(&e.author), Some(&e.time), None, Some(e.message.text.as_str())),
+			ChatEvent::PollUpdate(_) => (EventType::Poll, None, None, None, None),
+			// removals only come with an offset into the video
+			ChatEvent::MessageDeleted(e) => return self.removal_matches(None, e.video_offset_ms),
+			ChatEvent::AuthorRemoved(e) => return self.removal_matches(Some(&e.channel_id), e.video_offset_ms),
+			// always shown so they get reported
+			ChatEvent::Unknown(_) => return true,
+		};
+
+		if !self.types.is_empty() && !self.types.contains(&event_type) {
+			return false;
+		}
+		if (!self.authors.is_empty() || !self.channels.is_empty()) && !author.is_some_and(|author| self.author_matches(author)) {
+			return false;
+		}
+		if self.min_amount.is_some() && !self.amount_matches(amount, time, rate_table) {
+			return false;
+		}
+		if let Some(regex) = &self.message_regex {
+			if !message.is_some_and(|message| regex.is_match(message)) {
+				return false;
+			}
+		}
+		if self.since.is_some() || self.until.is_some() {
+			let Some(time) = time else { return false };
+			let offset = timestamps::video_offset_ms(time.video_offset_ms, time.timestamp_usec, release_timestamp);
+			return self.time_matches(timestamps::utc_from_usec(time.timestamp_usec), offset);
+		}
+		true
+	}
+
+	fn removal_matches(&self, channel_id: Option<&String>, offset: Option<i64>) -> bool {
+		if !self.types.is_empty() && !self.types.contains(&EventType::Removal) {
+			return false;
+		}
+		// a wipe only says which channel, never the name
+		if !self.authors.is_empty() {
+			return false;
+		}
+		if !self.channels.is_empty() && !channel_id.is_some_and(|channel_id| self.channels.contains(channel_id)) {
+			return false;
+		}
+		if self.min_amount.is_some() || self.message_regex.is_some() {
+			return false;
+		}
+		self.time_matches(None, offset)
+	}
+
+	fn author_matches(&self, author: &Author) -> bool {
+		self.channels.contains(&author.channel_id) || self.authors.iter().any(|name| name.to_lowercase() == author.name.to_lowercase())
+	}
+
+	fn amount_matches(&self, amount: Option<&Amount>, time: Option<&EventTime>, rate_table: Option<&RateTable>) -> bool {
+		let (Some(min), Some(amount)) = (&self.min_amount, amount) else { return false };
+		let min = Amount::from_value(&min.currency.clone().unwrap_or_else(|| self.base_currency.to_uppercase()), min.value);
+		let date = time.and_then(|time| rates::date_from_usec(time.timestamp_usec));
+		at_least(amount, &min, date, rate_table)
+	}
+
+	// bounds are inclusive, an absolute bound needs the time and an offset bound the offset
+	fn time_matches(&self, time: Option<DateTime<Utc>>, offset: Option<i64>) -> bool {
+		let after = |bound: &TimeBound| match bound {
+			TimeBound::Absolute(bound) => time.map(|time| time >= *bound),
+			TimeBound::Offset(bound) => offset.map(|offset| offset >= *bound),
+		};
+		let before = |bound: &TimeBound| match bound {
+			TimeBound::Absolute(bound) => time.map(|time| time <= *bound),
+			TimeBound::Offset(bound) => offset.map(|offset| offset <= *bound),
+		};
+		self.since.as_ref().is_none_or(|bound| after(bound).unwrap_or(false))
+			&& self.until.as_ref().is_none_or(|bound| before(bound).unwrap_or(false))
+	}
+}
+
+// also used by the reading queue
+// different currencies can only be compared with --rates, using the rates of the day
+pub fn at_least(amount: &Amount, min: &Amount, date: Option<NaiveDate>, rate_table: Option<&RateTable>) -> bool {
+	if amount.currency == min.currency {
+		return amount.minor_units >= min.minor_units;
+	}
+	let Some(rate_table) = rate_table else { return false };
+	match (rate_table.convert(amount, date), rate_table.convert(min, date)) {
+		(Some(value), Some(min_value)) => value.minor_units >= min_value.minor_units,
+		_ => false,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use superchat_extractor::event::{AuthorRemoved, Membership, Message, MessageDeleted, Poll, Sticker, Superchat};
+
+	// 2023-05-13T17:46:45Z, 5 seconds into the video
+	const USEC: i64 = 1_684_000_005_000_000;
+
+	fn author() -> Author {
+		Author { channel_id: "UCa".to_string(), name: "Alice".to_string(), photo_url: None, badges: Vec::new() }
+	}
+
+	fn time() -> EventTime {
+		EventTime { timestamp_usec: USEC, timestamp_text: None, video_offset_ms: Some(5000) }
+	}
+
+	fn superchat(amount: &str) -> ChatEvent {
+		ChatEvent::Superchat(Superchat {
+			id: "p1".to_string(),
+			author: author(),
+			time: time(),
+			amount_text: amount.to_string(),
+			amount: currency::parse_amount(amount),
+			message: Some(Message { text: "hello".to_string(), ..Default::default() }),
+			header_color: 0,
+			body_color: 0,
+		})
+	}
+
+	fn sticker() -> ChatEvent {
+		ChatEvent::Sticker(Sticker {
+			id: "s1".to_string(),
+			author: author(),
+			time: time(),
+			amount_text: "$5.00".to_string(),
+			amount: currency::parse_amount("$5.00"),
+			description: "hello".to_string(),
+			image_url: None,
+			background_color: 0,
+		})
+	}
+
+	fn membership() -> ChatEvent {
+		ChatEvent::Membership(Membership { id: "ms1".to_string(), author: author(), time: time(), months: None, message: None })
+	}
+
+	fn removals() -> [ChatEvent; 2] {
+		[
+			ChatEvent::MessageDeleted(MessageDeleted { target_id: "p1".to_string(), video_offset_ms: Some(5000) }),
+			ChatEvent::AuthorRemoved(AuthorRemoved { channel_id: "UCa".to_string(), video_offset_ms: Some(5000) }),
+		]
+	}
+
+	fn usd_filter(min_amount: &str) -> Filter {
+		Filter { min_amount: Some(parse_min_amount(min_amount).unwrap()), base_currency: "usd".to_string(), ..Default::default() }
+	}
+
+	#[test]
+	fn time_bounds() {
+		let absolute = |text| match parse_time_bound(text) {
+			Ok(TimeBound::Absolute(time)) => time.to_rfc3339(),
+			other => panic!("{text} isn't a point in time: {other:?}"),
+		};
+		assert_eq!(absolute("2023-05-13T17:46:45Z"), "2023-05-13T17:46:45+00:00");
+		assert_eq!(absolute("2023-05-13T17:46:45+09:00"), "2023-05-13T08:46:45+00:00");
+		assert_eq!(absolute("2023-05-13 17:46:45"), "2023-05-13T17:46:45+00:00");
+		assert_eq!(absolute("2023-05-13 17:46"), "2023-05-13T17:46:00+00:00");
+		assert_eq!(absolute("2023-05-13"), "2023-05-13T00:00:00+00:00");
+		assert!(matches!(parse_time_bound("1:23:45"), Ok(TimeBound::Offset(5_025_000))));
+		assert!(parse_time_bound("yesterday").is_err());
+		assert!(parse_time_bound("2023-13-01").is_err());
+	}
+
+	#[test]
+	fn offsets() {
+		let cases = [("5:00", Some(300_000)), ("1:23:45", Some(5_025_000)), ("-0:30", Some(-30_000)), ("0:90", Some(90_000))];
+		for (text, expected) in cases {
+			assert_eq!(parse_offset(text), expected, "{text}");
+		}
+		for invalid in ["5", "1:", ":30", "1:2:3:4", "a:bc", "+1:00", "1:-30"] {
+			assert_eq!(parse_offset(invalid), None, "{invalid}");
+		}
+	}
+
+	#[test]
+	fn min_amounts() {
+		let parsed = |text| parse_min_amount(text).map(|min| (min.currency, min.value));
+		assert_eq!(parsed("20"), Ok((None, 20.0)));
+		assert_eq!(parsed("$20"), Ok((Some("USD".to_string()), 20.0)));
+		assert_eq!(parsed("20 USD"), Ok((Some("USD".to_string()), 20.0)));
+		assert_eq!(parsed("¥1,000"), Ok((Some("JPY".to_string()), 1000.0)));
+		assert!(parsed("lots").is_err());
+	}
+
+	#[test]
+	fn events_a_filter_cant_check_dont_pass() {
+		let filter = usd_filter("1");
+		assert!(filter.matches(&superchat("$5.00"), None, None));
+		assert!(!filter.matches(&membership(), None, None));
+		let regex = Filter { message_regex: Some(Regex::new("hello").unwrap()), ..Default::default() };
+		assert!(regex.matches(&superchat("$5.00"), None, None));
+		// the sticker description isn't a message
+		assert!(!regex.matches(&sticker(), None, None));
+		let since = Filter { since: Some(parse_time_bound("0:01").unwrap()), ..Default::default() };
+		assert!(since.matches(&superchat("$5.00"), None, None));
+		assert!(!since.matches(&ChatEvent::PollUpdate(Poll { id: "poll".to_string(), question: Message::default(), metadata: Message::default(), choices: Vec::new() }), None, None));
+	}
+
+	#[test]
+	fn removals_only_match_filters_they_can_answer() {
+		let by_name = Filter { authors: vec!["alice".to_string()], ..Default::default() };
+		assert!(by_name.matches(&superchat("$5.00"), None, None));
+		// a wipe only says which channel
+		for removal in removals() {
+			assert!(!by_name.matches(&removal, None, None));
+		}
+		let by_channel = Filter { channels: vec!["UCa".to_string()], ..Default::default() };
+		let [deleted, wiped] = removals();
+		assert!(!by_channel.matches(&deleted, None, None));
+		assert!(by_channel.matches(&wiped, None, None));
+		let in_range = Filter { types: vec![EventType::Removal], until: Some(parse_time_bound("0:05").unwrap()), ..Default::default() };
+		assert!(in_range.matches(&deleted, None, None));
+		assert!(!usd_filter("1").matches(&wiped, None, None));
+	}
+
+	#[test]
+	fn different_currencies_need_rates() {
+		let path = std::env::temp_dir().join(format!("superchat-extractor-filter-{}.csv", std::process::id()));
+		std::fs::write(&path, "JPY,0.0072\n").unwrap();
+		let rate_table = RateTable::load(path.to_str().unwrap(), "USD").unwrap();
+		std::fs::remove_file(&path).unwrap();
+
+		let yen = superchat("¥1,000");
+		// 1000 JPY is 7.20 USD
+		for (min_amount, without_rates, with_rates) in [("$5", false, true), ("$8", false, false), ("7", false, true), ("¥1,000", true, true), ("¥1,001", false, false)] {
+			let filter = usd_filter(min_amount);
+			assert_eq!(filter.matches(&yen, None, None), without_rates, "{min_amount} without rates");
+			assert_eq!(filter.matches(&yen, Some(&rate_table), None), with_rates, "{min_amount} with rates");
+		}
+	}
+}
@@ -0,0 +1,178 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+// how long to wait before checking the file for new data again
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+pub enum Next {
+	// a complete line was put into the buffer
+	Line,
+	// nothing new was written yet, call again later
+	Idle,
+	// the file is done
+	End,
+}
+
+// reads a chat file line by line
+// in follow mode it keeps waiting for yt-dlp to append more lines
+pub struct ChatLines {
+	reader: BufReader<File>,
+	path: PathBuf,
+	idle_timeout: Option<Duration>,
+	last_data: Instant,
+	finished: bool,
+	// raw bytes so a character yt-dlp only wrote half of yet isn't lost
+	partial: Vec<u8>,
+}
+
+impl ChatLines {
+	pub fn open(path: &str, follow: bool, idle_timeout: Option<Duration>) -> std::io::Result<ChatLines> {
+		let file = File::open(path)?;
+		Ok(ChatLines {
+			reader: BufReader::new(file),
+			path: PathBuf::from(path),
+			idle_timeout,
+			last_data: Instant::now(),
+			finished: !follow,
+			partial: Vec::new(),
+		})
+	}
+
+	pub fn next_line(&mut self, line: &mut String) -> std::io::Result<Next> {
+		loop {
+			let read = self.reader.read_until(b'\n', &mut self.partial)?;
+			if read > 0 {
+				self.last_data = Instant::now();
+			}
+
+			if self.partial.ends_with(b"\n") {
+				self.partial.pop();
+				if self.partial.ends_with(b"\r") {
+					self.partial.pop();
+				}
+				self.take_line(line)?;
+				return Ok(Next::Line);
+			}
+
+			if read > 0 {
+				// got part of a line, see if the rest is there already
+				continue;
+			}
+
+			// reached the end of what has been written so far
+			if self.finished {
+				// the last line might not end with a newline
+				if self.partial.is_empty() {
+					return Ok(Next::End);
+				}
+				self.take_line(line)?;
+				return Ok(Next::Line);
+			}
+
+			// yt-dlp renames the .part file once the download is done
+			// our handle still points at the data so read whatever is left and stop
+			if !self.path.exists() {
+				self.finished = true;
+				continue;
+			}
+
+			if let Some(timeout) = self.idle_timeout {
+				if self.last_data.elapsed() >= timeout {
+					self.finished = true;
+					continue;
+				}
+			}
+
+			std::thread::sleep(POLL_INTERVAL);
+			return Ok(Next::Idle);
+		}
+	}
+
+	// only whole lines are decoded, a partly written one can end in the middle of a character
+	fn take_line(&mut self, line: &mut String) -> std::io::Result<()> {
+		let bytes = std::mem::take(&mut self.partial);
+		*line = String::from_utf8(bytes).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::Write;
+
+	fn temp_path(name: &str) -> PathBuf {
+		std::env::temp_dir().join(format!("superchat-extractor-follow-{}-{name}", std::process::id()))
+	}
+
+	#[test]
+	fn reads_a_last_line_without_newline() {
+		let path = temp_path("done.live_chat.json");
+		std::fs::write(&path, "first\r\nsecond").unwrap();
+		let mut lines = ChatLines::open(path.to_str().unwrap(), false, None).unwrap();
+		let mut line = String::new();
+		assert!(matches!(lines.next_line(&mut line).unwrap(), Next::Line));
+		assert_eq!(line, "first");
+		assert!(matches!(lines.next_line(&mut line).unwrap(), Next::Line));
+		assert_eq!(line, "second");
+		assert!(matches!(lines.next_line(&mut line).unwrap(), Next::End));
+		std::fs::remove_file(&path).unwrap();
+	}
+
+	#[test]
+	fn waits_for_the_rest_of_a_line_and_stops_after_the_part_rename() {
+		let path = temp_path("growing.live_chat.json.part");
+		let mut file = File::create(&path).unwrap();
+		file.write_all(b"first\nsec").unwrap();
+		let mut lines = ChatLines::open(path.to_str().unwrap(), true, None).unwrap();
+		let mut line = String::new();
+		assert!(matches!(lines.next_line(&mut line).unwrap(), Next::Line));
+		assert_eq!(line, "first");
+		// half a line isn't handed out yet
+		assert!(matches!(lines.next_line(&mut line).unwrap(), Next::Idle));
+
+		file.write_all(b"ond\nthi").unwrap();
+		assert!(matches!(lines.next_line(&mut line).unwrap(), Next::Line));
+		assert_eq!(line, "second");
+
+		// yt-dlp renaming the file, whatever was written last still comes out
+		let done = temp_path("growing.live_chat.json");
+		std::fs::rename(&path, &done).unwrap();
+		file.write_all(b"rd").unwrap();
+		assert!(matches!(lines.next_line(&mut line).unwrap(), Next::Line));
+		assert_eq!(line, "third");
+		assert!(matches!(lines.next_line(&mut line).unwrap(), Next::End));
+		std::fs::remove_file(&done).unwrap();
+	}
+
+	#[test]
+	fn waits_for_the_rest_of_a_character() {
+		let path = temp_path("emoji.live_chat.json.part");
+		let mut file = File::create(&path).unwrap();
+		let emoji = "👋".as_bytes();
+		file.write_all(b"hi ").unwrap();
+		file.write_all(&emoji[..2]).unwrap();
+		let mut lines = ChatLines::open(path.to_str().unwrap(), true, None).unwrap();
+		let mut line = String::new();
+		assert!(matches!(lines.next_line(&mut line).unwrap(), Next::Idle));
+
+		file.write_all(&emoji[2..]).unwrap();
+		file.write_all(b"\n").unwrap();
+		assert!(matches!(lines.next_line(&mut line).unwrap(), Next::Line));
+		assert_eq!(line, "hi 👋");
+		std::fs::remove_file(&path).unwrap();
+	}
+
+	#[test]
+	fn stops_after_the_idle_timeout() {
+		let path = temp_path("stalled.live_chat.json.part");
+		std::fs::write(&path, "only\n").unwrap();
+		let mut lines = ChatLines::open(path.to_str().unwrap(), true, Some(Duration::ZERO)).unwrap();
+		let mut line = String::new();
+		assert!(matches!(lines.next_line(&mut line).unwrap(), Next::Line));
+		assert!(matches!(lines.next_line(&mut line).unwrap(), Next::End));
+		std::fs::remove_file(&path).unwrap();
+	}
+}
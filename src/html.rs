@@ -0,0 +1,227 @@
+// single file html report where every paid event is a card in youtube's colors
+// no javascript and no stylesheets to fetch, only the images come from youtube
+
+use superchat_extractor::export::ExportStructs;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+const STYLE: &str = "
+body { background: #0f0f0f; color: #f1f1f1; font-family: Roboto, Arial, sans-serif; margin: 0 auto; max-width: 720px; padding: 24px; }
+header h1 { font-size: 1.4em; margin-bottom: 8px; }
+.stats { display: grid; grid-template-columns: max-content auto; gap: 2px 16px; margin: 0 0 24px; }
+.stats dt { color: #aaa; }
+.stats dd { margin: 0; }
+.card { border-radius: 8px; margin-bottom: 12px; overflow: hidden; }
+.card-header { align-items: center; background: var(--header); color: var(--header-text); display: flex; gap: 12px; padding: 8px 16px; }
+.card-body { background: var(--body); color: var(--body-text); padding: 8px 16px; white-space: pre-wrap; word-wrap: break-word; }
+.card-body:empty { display: none; }
+.avatar { border-radius: 50%; height: 40px; width: 40px; }
+.name { font-size: 0.9em; opacity: 0.8; }
+.amount { font-weight: bold; }
+.time { font-size: 0.8em; margin-left: auto; opacity: 0.8; }
+.sticker { height: 72px; }
+.emoji { height: 1.4em; vertical-align: middle; }
+a { color: inherit; text-decoration: none; }
+";
+
+pub struct Report<'a> {
+	pub title: &'a str,
+	// label and value rows for the summary at the top
+	pub stats: Vec<(String, String)>,
+	// custom emoji shortcut without the colons -> image url, sorted so they are always put back in the same order
+	pub emoji_images: &'a BTreeMap<String, String>,
+}
+
+impl Report<'_> {
+	pub fn render<'a>(&self, events: impl Iterator<Item = &'a ExportStructs>) -> String {
+		let mut html = String::new();
+		let _ = write!(
+			html,
+			"<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n",
+			escape(self.title),
+			STYLE
+		);
+
+		let _ = writeln!(html, "<header>\n<h1>{}</h1>\n<dl class=\"stats\">", escape(self.title));
+		for (label, value) in self.stats.iter() {
+			let _ = writeln!(html, "<dt>{}</dt><dd>{}</dd>", escape(label), escape(value));
+		}
+		html.push_str("</dl>\n</header>\n<main>\n");
+
+		for event in events {
+			self.render_card(&mut html, event);
+		}
+
+		html.push_str("</main>\n</body>\n</html>\n");
+		html
+	}
+
+	fn render_card(&self, html: &mut String, event: &ExportStructs) {
+		// the headline goes next to the name, the body under it
+		let (username, channel_id, time, video_id, video_offset_ms, thumbnail_url, header_color, body_color, headline, body) = match event {
+			ExportStructs::Donation(e) => (
+				&e.username, &e.channel_id, &e.time, &e.video_id, e.video_offset_ms, &e.thumbnail_url, e.header_color, e.body_color,
+				escape(&e.amount),
+				e.message.as_deref().map(|message| self.message_html(message)).unwrap_or_default(),
+			),
+			ExportStructs::Sticker(e) => (
+				&e.username, &e.channel_id, &e.time, &e.video_id, e.video_offset_ms, &e.thumbnail_url, e.header_color, e.body_color,
+				escape(&e.sticker_cost),
+				format!("<img class=\"sticker\" src=\"{}\" alt=\"{}\" title=\"{}\">", escape(&e.sticker_image_url), escape(&e.sticker_description), escape(&e.sticker_description)),
+			),
+			ExportStructs::Membership(e) => (
+				&e.username, &e.channel_id, &e.time, &e.video_id, e.video_offset_ms, &e.thumbnail_url, e.header_color, e.body_color,
+				escape(&e.months),
+				e.message.as_deref().map(|message| self.message_html(message)).unwrap_or_default(),
+			),
+			ExportStructs::GiftingMembership(e) => (
+				&e.username, &e.channel_id, &e.time, &e.video_id, e.video_offset_ms, &e.thumbnail_url, e.header_color, e.body_color,
+				format!("gifted {} memberships", escape(&e.number)),
+				String::new(),
+			),
+			ExportStructs::GiftMembership(e) => (
+				&e.username, &e.channel_id, &e.time, &e.video_id, e.video_offset_ms, &e.thumbnail_url, e.header_color, e.body_color,
+				format!("received a gift membership from {}", escape(&e.sender)),
+				String::new(),
+			),
+			// normal chat doesn't get a card
+			ExportStructs::TextMessage(_) => return,
+		};
+
+		// links to the moment in the vod when we know where that is
+		let time = match (video_id, video_offset_ms) {
+			(Some(video_id), Some(offset)) => format!(
+				"<a href=\"https://youtu.be/{}?t={}\">{}</a>",
+				escape(video_id), (offset / 1000).max(0), escape(time),
+			),
+			_ => escape(time),
+		};
+
+		let _ = write!(
+			html,
+			"<article class=\"card\" style=\"--header: {}; --header-text: {}; --body: {}; --body-text: {};\">\n\
+			<div class=\"card-header\"><img class=\"avatar\" src=\"{}\" alt=\"\">\
+			<div><div class=\"name\"><a href=\"https://youtube.com/channel/{}\">{}</a></div><div class=\"amount\">{}</div></div>\
+			<span class=\"time\">{}</span></div>\n\
+			<div class=\"card-body\">{}</div>\n</article>\n",
+			css_color(header_color), text_color(header_color), css_color(body_color), text_color(body_color),
+			escape(thumbnail_url), escape(channel_id), escape(username), headline, time, body,
+		);
+	}
+
+	// messages were flattened to text with :shortcut: for custom emojis, put the images back
+	fn message_html(&self, message: &str) -> String {
+		let mut html = escape(message);
+		for (label, url) in self.emoji_images.iter() {
+			let shortcut = format!(":{}:", escape(label));
+			if html.contains(&shortcut) {
+				let image = format!("<img class=\"emoji\" src=\"{}\" alt=\"{}\" title=\"{}\">", escape(url), shortcut, shortcut);
+				html = html.replace(&shortcut, &image);
+			}
+		}
+		html
+	}
+}
+
+fn escape(text: &str) -> String {
+	let mut escaped = String::with_capacity(text.len());
+	for c in text.chars() {
+		match c {
+			'&' => escaped.push_str("&amp;"),
+			'<' => escaped.push_str("&lt;"),
+			'>' => escaped.push_str("&gt;"),
+			'"' => escaped.push_str("&quot;"),
+			'\'' => escaped.push_str("&#39;"),
+			_ => escaped.push(c),
+		}
+	}
+	escaped
+}
+
+// colors are argb for superchats and plain rgb for memberships, the alpha is always ff so ignore it
+fn css_color(color: i64) -> String {
+	format!("#{:06x}", color & 0xffffff)
+}
+
+// black or white, whichever is easier to read on the background
+fn text_color(background: i64) -> &'static str {
+	let red = (background >> 16) & 0xff;
+	let green = (background >> 8) & 0xff;
+	let blue = background & 0xff;
+	if red * 299 + green * 587 + blue * 114 > 128_000 {
+		"#000000"
+	} else {
+		"#ffffff"
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use superchat_extractor::export;
+
+	fn donation(username: &str, message: &str) -> ExportStructs {
+		let event = serde_json::json!({
+			"type": "Donation", "id": "d1", "time": "1:05", "username": username, "channel_id": "UCaaa", "amount": "$5.00",
+			"message": message, "header_color": 0xffe62117_i64, "body_color": 0xffd00000_i64, "thumbnail_url": "https://yt3.ggpht.com/a",
+			"video_id": "dQw4w9WgXcQ", "video_offset_ms": 65_400,
+		});
+		export::parse_line(&event.to_string()).unwrap()
+	}
+
+	fn card(report: &Report, event: &ExportStructs) -> String {
+		let mut html = String::new();
+		report.render_card(&mut html, event);
+		html
+	}
+
+	#[test]
+	fn escapes_markup_and_quotes() {
+		assert_eq!(escape(r#"<b>"Tom" & 'Jerry'</b>"#), "&lt;b&gt;&quot;Tom&quot; &amp; &#39;Jerry&#39;&lt;/b&gt;");
+		assert_eq!(escape("plain 👋"), "plain 👋");
+	}
+
+	#[test]
+	fn drops_the_alpha_and_keeps_leading_zeros() {
+		assert_eq!(css_color(0xff00a0ff), "#00a0ff");
+		assert_eq!(css_color(0x0000ff), "#0000ff");
+		assert_eq!(css_color(0), "#000000");
+		assert_eq!(text_color(0xffffffff), "#000000");
+		assert_eq!(text_color(0xff1e88e5), "#ffffff");
+	}
+
+	#[test]
+	fn cards_escape_what_chat_sent() {
+		let emoji_images = BTreeMap::new();
+		let report = Report { title: "", stats: Vec::new(), emoji_images: &emoji_images };
+		let html = card(&report, &donation("<script>alert(1)</script>", "\"><img src=x onerror=alert(1)>"));
+		assert!(!html.contains("<script>"));
+		assert!(!html.contains("<img src=x"));
+		assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+		assert!(html.contains("--header: #e62117; --header-text: #ffffff; --body: #d00000;"));
+		// the time links to the second in the vod
+		assert!(html.contains("<a href=\"https://youtu.be/dQw4w9WgXcQ?t=65\">1:05</a>"));
+	}
+
+	#[test]
+	fn chat_gets_no_card() {
+		let emoji_images = BTreeMap::new();
+		let report = Report { title: "", stats: Vec::new(), emoji_images: &emoji_images };
+		let message = export::parse_line(r#"{"type":"TextMessage","id":"m1","username":"Ann","channel_id":"UCa","time":"0:05","badges":[],"message":"hi"}"#).unwrap();
+		assert_eq!(card(&report, &message), "");
+	}
+
+	#[test]
+	fn puts_custom_emoji_images_back() {
+		let emoji_images = BTreeMap::from([
+			("wave".to_string(), "https://yt3.ggpht.com/wave".to_string()),
+			("_cat<".to_string(), "https://yt3.ggpht.com/cat?a=1&b=2".to_string()),
+		]);
+		let report = Report { title: "", stats: Vec::new(), emoji_images: &emoji_images };
+		assert_eq!(
+			report.message_html("hi :wave: :_cat<: <b> :unknown:"),
+			"hi <img class=\"emoji\" src=\"https://yt3.ggpht.com/wave\" alt=\":wave:\" title=\":wave:\"> \
+			<img class=\"emoji\" src=\"https://yt3.ggpht.com/cat?a=1&amp;b=2\" alt=\":_cat&lt;:\" title=\":_cat&lt;:\"> &lt;b&gt; :unknown:",
+		);
+	}
+}
@@ -0,0 +1,266 @@
+// who spent the most, grouped by channel id so name changes don't split someone up
+// amounts in different currencies can only be compared after --rates converted them,
+// without it donors are ranked within each currency instead
+
+use superchat_extractor::currency::{self, Amount};
+use superchat_extractor::export::ExportStructs;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Default)]
+pub struct Donor {
+	pub channel_id: String,
+	// the name from their most recent event
+	pub username: String,
+	pub events: usize,
+	pub superchats: usize,
+	pub stickers: usize,
+	pub memberships: usize,
+	pub gifted_memberships: u64,
+	// spend per currency code, in minor units
+	pub totals: BTreeMap<String, i64>,
+	// spend converted with --rates, in minor units of base_currency
+	pub converted_total: Option<i64>,
+	pub base_currency: Option<String>,
+}
+
+impl Donor {
+	// "1000 JPY, 5.00 USD"
+	pub fn totals_string(&self) -> String {
+		self.totals
+			.iter()
+			.map(|(currency, minor_units)| Amount { currency: currency.clone(), minor_units: *minor_units }.to_string())
+			.collect::<Vec<_>>()
+			.join(", ")
+	}
+
+	pub fn converted(&self) -> Option<Amount> {
+		match (self.converted_total, &self.base_currency) {
+			(Some(minor_units), Some(base)) => Some(Amount { currency: base.clone(), minor_units }),
+			_ => None,
+		}
+	}
+}
+
+// what the .json leaderboard has per donor, amounts in whole units like the export
+#[derive(Serialize)]
+struct DonorJson<'a> {
+	channel_id: &'a str,
+	username: &'a str,
+	events: usize,
+	superchats: usize,
+	stickers: usize,
+	memberships: usize,
+	gifted_memberships: u64,
+	totals: BTreeMap<&'a str, f64>,
+	converted_total: Option<f64>,
+	base_currency: Option<&'a str>,
+}
+
+impl<'a> From<&'a Donor> for DonorJson<'a> {
+	fn from(donor: &'a Donor) -> DonorJson<'a> {
+		DonorJson {
+			channel_id: &donor.channel_id,
+			username: &donor.username,
+			events: donor.events,
+			superchats: donor.superchats,
+			stickers: donor.stickers,
+			memberships: donor.memberships,
+			gifted_memberships: donor.gifted_memberships,
+			totals: donor.totals.iter().map(|(currency, minor_units)| (currency.as_str(), Amount { currency: currency.clone(), minor_units: *minor_units }.value())).collect(),
+			converted_total: donor.converted().map(|amount| amount.value()),
+			base_currency: donor.base_currency.as_deref(),
+		}
+	}
+}
+
+// totals added up while reading, so the events don't have to be kept around for it
+// events have to be added in the order they happened for the latest username to be right
+#[derive(Default)]
+pub struct Leaderboard {
+	donors: HashMap<String, Donor>,
+}
+
+impl Leaderboard {
+	pub fn add(&mut self, event: &ExportStructs) {
+		let (channel_id, username) = match event {
+			ExportStructs::Donation(e) => (&e.channel_id, &e.username),
+			ExportStructs::Sticker(e) => (&e.channel_id, &e.username),
+			ExportStructs::GiftingMembership(e) => (&e.channel_id, &e.username),
+			ExportStructs::Membership(e) => (&e.channel_id, &e.username),
+			// receiving a gift or chatting doesn't cost anything
+			ExportStructs::GiftMembership(_) | ExportStructs::TextMessage(_) => return,
+		};
+		let donor = self.donors.entry(channel_id.clone()).or_insert_with(|| Donor {
+			channel_id: channel_id.clone(),
+			..Default::default()
+		});
+		donor.username = username.clone();
+		donor.events += 1;
+
+		match event {
+			ExportStructs::Donation(e) => {
+				donor.superchats += 1;
+				add_spend(donor, &e.currency, e.amount_value, e.converted_value, &e.base_currency);
+			},
+			ExportStructs::Sticker(e) => {
+				donor.stickers += 1;
+				add_spend(donor, &e.currency, e.amount_value, e.converted_value, &e.base_currency);
+			},
+			ExportStructs::GiftingMembership(e) => match e.number.parse::<u64>() {
+				Ok(number) => donor.gifted_memberships += number,
+				Err(_) => eprintln!("leaderboard: could not read the number of memberships {} gifted: {:?}, not counted", e.username, e.number),
+			},
+			ExportStructs::Membership(_) => {
+				donor.memberships += 1;
+			},
+			ExportStructs::GiftMembership(_) | ExportStructs::TextMessage(_) => {},
+		}
+	}
+
+	// every donor, best first
+	pub fn finish(self) -> Vec<Donor> {
+		let mut donors: Vec<Donor> = self.donors.into_values().collect();
+		if donors.iter().any(|donor| donor.converted_total.is_some()) {
+			donors.sort_by(|a, b| {
+				b.converted_total.unwrap_or(0).cmp(&a.converted_total.unwrap_or(0))
+					.then(b.gifted_memberships.cmp(&a.gifted_memberships))
+					.then(b.events.cmp(&a.events))
+					.then(a.channel_id.cmp(&b.channel_id))
+			});
+		} else {
+			// grouped by the first currency someone paid in, biggest spend first within it
+			// donors who only gifted or joined come last
+			donors.sort_by(|a, b| {
+				let (a_currency, a_total) = first_total(a);
+				let (b_currency, b_total) = first_total(b);
+				a_currency.is_none().cmp(&b_currency.is_none())
+					.then(a_currency.cmp(&b_currency))
+					.then(b_total.cmp(&a_total))
+					.then(b.gifted_memberships.cmp(&a.gifted_memberships))
+					.then(b.events.cmp(&a.events))
+					.then(a.channel_id.cmp(&b.channel_id))
+			});
+		}
+		donors
+	}
+}
+
+fn first_total(donor: &Donor) -> (Option<&String>, i64) {
+	donor.totals.iter().next().map(|(currency, total)| (Some(currency), *total)).unwrap_or((None, 0))
+}
+
+// every currency someone paid in, for ranking without --rates
+pub fn currencies(donors: &[Donor]) -> Vec<&str> {
+	let mut currencies: Vec<&str> = donors.iter().flat_map(|donor| donor.totals.keys().map(String::as_str)).collect();
+	currencies.sort();
+	currencies.dedup();
+	currencies
+}
+
+// donors who paid in this currency, biggest spend in it first
+pub fn ranked_in<'a>(donors: &'a [Donor], currency: &str) -> Vec<&'a Donor> {
+	let mut ranked: Vec<&Donor> = donors.iter().filter(|donor| donor.totals.contains_key(currency)).collect();
+	ranked.sort_by(|a, b| b.totals[currency].cmp(&a.totals[currency]).then(a.channel_id.cmp(&b.channel_id)));
+	ranked
+}
+
+// the export has whole units, back to minor units so the totals add up exactly
+fn add_spend(donor: &mut Donor, currency: &Option<String>, value: Option<f64>, converted: Option<f64>, base_currency: &Option<String>) {
+	if let (Some(currency), Some(value)) = (currency, value) {
+		*donor.totals.entry(currency.clone()).or_insert(0) += Amount::from_value(currency, value).minor_units;
+	}
+	if let (Some(converted), Some(base)) = (converted, base_currency) {
+		*donor.converted_total.get_or_insert(0) += Amount::from_value(base, converted).minor_units;
+		donor.base_currency = Some(base.clone());
+	}
+}
+
+// .json gets an array of donors, anything else is written as csv
+pub fn write(donors: &[Donor], path: &str) -> std::io::Result<()> {
+	let file = std::fs::File::create(path)?;
+	if path.to_lowercase().ends_with(".json") {
+		let donors: Vec<DonorJson> = donors.iter().map(DonorJson::from).collect();
+		serde_json::to_writer_pretty(file, &donors)?;
+		return Ok(());
+	}
+
+	let mut writer = csv::Writer::from_writer(file);
+	writer.write_record([
+		"channel_id", "username", "events", "superchats", "stickers", "memberships",
+		"gifted_memberships", "totals", "converted_total", "base_currency",
+	])?;
+	for donor in donors {
+		writer.write_record([
+			donor.channel_id.clone(),
+			donor.username.clone(),
+			donor.events.to_string(),
+			donor.superchats.to_string(),
+			donor.stickers.to_string(),
+			donor.memberships.to_string(),
+			donor.gifted_memberships.to_string(),
+			donor.totals_string(),
+			donor.converted().map(|total| format!("{:.*}", currency::exponent(&total.currency) as usize, total.value())).unwrap_or_default(),
+			donor.base_currency.clone().unwrap_or_default(),
+		])?;
+	}
+	writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use superchat_extractor::export;
+
+	fn donation(channel_id: &str, amount: &str) -> ExportStructs {
+		export::parse_line(&format!(
+			r#"{{"type":"Donation","username":"{channel_id}","channel_id":"{channel_id}","amount":"{amount}","message":null,"header_color":0,"body_color":0,"thumbnail_url":""}}"#
+		)).unwrap()
+	}
+
+	fn build(events: &[ExportStructs]) -> Vec<Donor> {
+		let mut leaderboard = Leaderboard::default();
+		for event in events {
+			leaderboard.add(event);
+		}
+		leaderboard.finish()
+	}
+
+	#[test]
+	fn ranks_by_amount_within_a_currency_without_rates() {
+		let events = [
+			donation("UCsmall", "$1.00"), donation("UCsmall", "$1.00"), donation("UCsmall", "$1.00"),
+			donation("UCbig", "$500.00"),
+			donation("UCyen", "¥1,000"),
+		];
+		let donors = build(&events);
+		assert_eq!(currencies(&donors), vec!["JPY", "USD"]);
+		let usd: Vec<&str> = ranked_in(&donors, "USD").iter().map(|donor| donor.channel_id.as_str()).collect();
+		assert_eq!(usd, vec!["UCbig", "UCsmall"]);
+		assert_eq!(donors.iter().find(|donor| donor.channel_id == "UCsmall").unwrap().totals_string(), "3.00 USD");
+	}
+
+	#[test]
+	fn ranks_by_converted_total_with_rates() {
+		let mut events = [donation("UCyen", "¥1,000"), donation("UCbig", "$5.00")];
+		for (event, converted) in events.iter_mut().zip([7.2, 5.0]) {
+			if let ExportStructs::Donation(e) = event {
+				e.converted_value = Some(converted);
+				e.base_currency = Some("USD".to_string());
+			}
+		}
+		let donors = build(&events);
+		assert_eq!(donors[0].channel_id, "UCyen");
+		assert_eq!(donors[0].converted().unwrap().to_string(), "7.20 USD");
+	}
+
+	#[test]
+	fn does_not_count_unreadable_gift_numbers() {
+		let gift = |number: &str| export::parse_line(&format!(
+			r#"{{"type":"GiftingMembership","username":"Sam","channel_id":"UCsam","number":"{number}","header_color":0,"body_color":0,"thumbnail_url":""}}"#
+		)).unwrap();
+		let donors = build(&[gift("5"), gift(""), gift("10")]);
+		assert_eq!(donors[0].gifted_memberships, 15);
+		assert_eq!(donors[0].events, 3);
+	}
+}
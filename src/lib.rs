@@ -0,0 +1,18 @@
+//! Reads the `.live_chat.json` files yt-dlp writes for youtube streams and turns them into typed events.
+//!
+//! Use [`parse_line`] for single lines or [`ChatReader`] to go through a whole file.
+//! With the `async` feature `ChatStream` does the same for tokio programs and can follow a file yt-dlp is still writing.
+//! The records the `superchat-extractor` binary writes out are in [`export`], along with a reader for its output files.
+
+pub mod currency;
+pub mod event;
+pub mod export;
+mod parse;
+#[cfg(feature = "async")]
+mod stream;
+mod youtube;
+
+pub use event::ChatEvent;
+pub use parse::{parse_line, ChatReader, Error};
+#[cfg(feature = "async")]
+pub use stream::ChatStream;
@@ -0,0 +1,241 @@
+// pushes events to the serve subcommand as soon as they are parsed
+// if the server isn't there events are buffered and sent once it comes back
+// see protocol.rs for what goes over the socket
+
+use crate::metadata::StreamMetadata;
+use crate::protocol::{self, Message};
+use superchat_extractor::export::ExportStructs;
+use interprocess::local_socket::LocalSocketStream;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+// how long to wait between attempts to reach the server
+const RETRY_INTERVAL: Duration = Duration::from_secs(2);
+// how many times to try to deliver what's left before giving up at the end
+const FINAL_ATTEMPTS: u32 = 5;
+// oldest events get dropped after this so a missing server can't eat all the memory
+const MAX_BUFFERED: usize = 100_000;
+// how long the connection can stay quiet before telling the server we're still here
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+
+pub struct LiveClient {
+	socket: String,
+	// announced on every new connection so the server knows which stream the events belong to
+	stream: String,
+	metadata: StreamMetadata,
+	connection: Option<LocalSocketStream>,
+	// encoded event messages that haven't been written yet
+	buffer: VecDeque<String>,
+	// sequence number of the next event
+	seq: u64,
+	retry_interval: Duration,
+	next_attempt: Instant,
+	last_write: Instant,
+	dropped: usize,
+	// only complain once per outage
+	warned: bool,
+}
+
+impl LiveClient {
+	pub fn new(socket: &str, stream: String, metadata: StreamMetadata) -> LiveClient {
+		let mut client = LiveClient {
+			socket: socket.to_string(),
+			stream,
+			metadata,
+			connection: None,
+			buffer: VecDeque::new(),
+			seq: 0,
+			retry_interval: RETRY_INTERVAL,
+			next_attempt: Instant::now(),
+			last_write: Instant::now(),
+			dropped: 0,
+			warned: false,
+		};
+		client.flush();
+		client
+	}
+
+	pub fn send(&mut self, event: ExportStructs) {
+		if self.buffer.len() >= MAX_BUFFERED {
+			self.buffer.pop_front();
+			self.dropped += 1;
+		}
+		let message = Message::Event { stream: self.stream.clone(), seq: self.seq, event };
+		self.seq += 1;
+		self.buffer.push_back(protocol::encode(&message));
+		self.flush();
+	}
+
+	// called while the chat is quiet, retries buffered events and keeps the connection alive
+	pub fn heartbeat(&mut self) {
+		if self.flush() && self.last_write.elapsed() >= HEARTBEAT_INTERVAL {
+			self.write(&protocol::encode(&Message::Heartbeat));
+		}
+	}
+
+	// delivers everything buffered, reconnecting if it's time to try again
+	// returns true when nothing is left in the buffer
+	pub fn flush(&mut self) -> bool {
+		if self.connection.is_none() && !self.connect() {
+			return self.buffer.is_empty();
+		}
+		while let Some(line) = self.buffer.pop_front() {
+			if !self.write(&line) {
+				self.buffer.push_front(line);
+				return false;
+			}
+		}
+		true
+	}
+
+	// last chance to get buffered events out before exiting
+	// returns how many events never made it to the server
+	pub fn finish(mut self) -> usize {
+		for _ in 0..FINAL_ATTEMPTS {
+			if self.flush() {
+				break;
+			}
+			std::thread::sleep(self.next_attempt.saturating_duration_since(Instant::now()));
+		}
+		if self.buffer.is_empty() && self.connection.is_some() {
+			let end = Message::StreamEnd { stream: self.stream.clone(), events: self.seq };
+			self.write(&protocol::encode(&end));
+		}
+		if !self.buffer.is_empty() {
+			eprintln!("could not send {} events to the live server", self.buffer.len());
+		}
+		if self.dropped > 0 {
+			eprintln!("dropped {} events while the live server was unreachable", self.dropped);
+		}
+		self.buffer.len()
+	}
+
+	fn connect(&mut self) -> bool {
+		if Instant::now() < self.next_attempt {
+			return false;
+		}
+		let hello = protocol::encode(&Message::Hello { client: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")) });
+		let start = protocol::encode(&Message::StreamStart { stream: self.stream.clone(), metadata: self.metadata.clone() });
+		let result = LocalSocketStream::connect(self.socket.as_str()).and_then(|mut connection| {
+			connection.write_all(format!("{hello}\n{start}\n").as_bytes())?;
+			Ok(connection)
+		});
+		match result {
+			Ok(connection) => {
+				if self.warned {
+					eprintln!("reconnected to live server, sending {} buffered events", self.buffer.len());
+				}
+				self.connection = Some(connection);
+				self.last_write = Instant::now();
+				self.warned = false;
+				true
+			},
+			Err(e) => {
+				self.disconnected(e);
+				false
+			},
+		}
+	}
+
+	// one message on the connection, false if it broke
+	fn write(&mut self, line: &str) -> bool {
+		let Some(connection) = self.connection.as_mut() else {
+			return false;
+		};
+		match connection.write_all(format!("{line}\n").as_bytes()) {
+			Ok(()) => {
+				self.last_write = Instant::now();
+				true
+			},
+			Err(e) => {
+				self.disconnected(e);
+				false
+			},
+		}
+	}
+
+	fn disconnected(&mut self, e: std::io::Error) {
+		if !self.warned {
+			eprintln!("lost connection to live server, buffering events: {e}");
+			self.warned = true;
+		}
+		self.connection = None;
+		self.next_attempt = Instant::now() + self.retry_interval;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use interprocess::local_socket::LocalSocketListener;
+	use std::io::{BufRead, BufReader, Lines};
+
+	const DONATION: &str = r#"{"type":"Donation","id":"a","username":"someone","channel_id":"UCaaa","amount":"$5.00","currency":"USD","amount_value":5.0,"message":null,"header_color":0,"body_color":0,"thumbnail_url":""}"#;
+
+	fn event() -> ExportStructs {
+		serde_json::from_str(DONATION).unwrap()
+	}
+
+	fn socket_name(name: &str) -> String {
+		format!("@superchat-extractor-live-test-{}-{name}", std::process::id())
+	}
+
+	fn next_message(lines: &mut Lines<BufReader<LocalSocketStream>>) -> Message {
+		protocol::decode(&lines.next().unwrap().unwrap()).unwrap()
+	}
+
+	fn next_seq(lines: &mut Lines<BufReader<LocalSocketStream>>) -> u64 {
+		match next_message(lines) {
+			Message::Event { seq, .. } => seq,
+			message => panic!("expected an event, got {message:?}"),
+		}
+	}
+
+	#[test]
+	fn resends_buffered_events_with_their_seq_after_a_reconnect() {
+		let socket = socket_name("reconnect");
+		let listener = LocalSocketListener::bind(socket.as_str()).unwrap();
+		let mut client = LiveClient::new(&socket, "s".to_string(), StreamMetadata::default());
+		let mut lines = BufReader::new(listener.accept().unwrap()).lines();
+		client.send(event());
+		assert!(matches!(next_message(&mut lines), Message::Hello { .. }));
+		assert!(matches!(next_message(&mut lines), Message::StreamStart { .. }));
+		assert_eq!(next_seq(&mut lines), 0);
+
+		// the server goes away mid-stream, what comes next waits in the buffer
+		drop(lines);
+		drop(listener);
+		client.send(event());
+		client.send(event());
+		assert!(client.connection.is_none());
+		assert_eq!(client.buffer.len(), 2);
+
+		let listener = LocalSocketListener::bind(socket.as_str()).unwrap();
+		client.next_attempt = Instant::now();
+		assert!(client.flush());
+		let mut lines = BufReader::new(listener.accept().unwrap()).lines();
+		// a new connection starts over with hello and stream_start, the events keep their seq
+		assert!(matches!(next_message(&mut lines), Message::Hello { .. }));
+		assert!(matches!(next_message(&mut lines), Message::StreamStart { .. }));
+		assert_eq!(next_seq(&mut lines), 1);
+		assert_eq!(next_seq(&mut lines), 2);
+
+		assert_eq!(client.finish(), 0);
+		assert!(matches!(next_message(&mut lines), Message::StreamEnd { events: 3, .. }));
+	}
+
+	#[test]
+	fn finish_gives_up_after_its_attempts() {
+		let mut client = LiveClient::new(&socket_name("missing"), "s".to_string(), StreamMetadata::default());
+		client.retry_interval = Duration::from_millis(20);
+		client.next_attempt = Instant::now();
+		client.send(event());
+		client.send(event());
+
+		let started = Instant::now();
+		assert_eq!(client.finish(), 2);
+		// one wait between each attempt
+		assert!(started.elapsed() >= Duration::from_millis(20) * (FINAL_ATTEMPTS - 1));
+	}
+}
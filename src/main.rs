@@ -1,542 +1,368 @@
-#![allow(non_snake_case)]
-
-use clap::{Parser};
-use serde::{Deserialize, Serialize};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use colored::Colorize;
 use std::io::Write;
+use superchat_extractor::currency::Amount;
+use superchat_extractor::export::{self, ExportStructs};
+use superchat_extractor::{event, ChatEvent};
+
+mod csv_output;
+mod drift;
+mod filter;
+mod follow;
+mod html;
+mod leaderboard;
+mod live;
+mod markdown;
+mod metadata;
+mod modlog;
+mod protocol;
+mod quarantine;
+mod queue;
+mod rates;
+mod server;
+mod sqlite;
+mod timestamps;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about=None)]
 struct Cli {
-    #[arg(long)]
-	#[clap(allow_hyphen_values = true)]
-    file: String,
-
-    #[arg(long)]
-	#[clap(allow_hyphen_values = true)]
-    outputfile: Option<String>,
-
-	#[arg(long)]
-	dontprint: bool,
-
-	#[arg(long)]
-	live: bool,
+	#[command(subcommand)]
+	command: Command,
 }
 
-#[derive(Debug, Deserialize)]
-struct ChatItem {
-    //isLive: Option<bool>,
-    //videoOffsetTimeMsec: Option<String>,
-    replayChatItemAction: ReplayChatItemAction,
-}
+#[derive(Subcommand, Debug)]
+enum Command {
+	/// read a chat file, print its events and totals and write the events out
+	Extract(ExtractArgs),
 
-#[derive(Debug, Deserialize)]
-struct ReplayChatItemAction {
-    actions: Vec<Action>,
-}
+	/// print totals, top donors and removals of a chat file without its events, or write them to files
+	Stats(StatsArgs),
 
+	/// render a report from a file written by extract --outputfile
+	Report(ReportArgs),
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(untagged)]
-enum Action {
-    AddChatItemAction {
-        addChatItemAction: AddChatItemAction,
-        clickTrackingParams: Option<String>,
-    },
-	AddBannerToLiveChatCommand {
-		addBannerToLiveChatCommand: serde_json::Value,
-		clickTrackingParams: Option<String>,
-	},
-	AddLiveChatTickerItemAction {
-		addLiveChatTickerItemAction: serde_json::Value,
-		clickTrackingParams: Option<String>,
-	},
-	CloseLiveChatActionPanelAction {
-		closeLiveChatActionPanelAction: serde_json::Value,
-		clickTrackingParams: Option<String>,
-	},
-	LiveChatReportModerationStateCommand {
-		liveChatReportModerationStateCommand: serde_json::Value,
-		clickTrackingParams: Option<String>,
-	},
-	RemoveBannerForLiveChatCommand {
-		removeBannerForLiveChatCommand: serde_json::Value,
-		clickTrackingParams: Option<String>,
-	},
-	RemoveChatItemAction {
-		removeChatItemAction: RemoveChatItem,
-		clickTrackingParams: Option<String>,
-	},
-	RemoveChatItemByAuthorAction {
-		removeChatItemByAuthorAction: RemoveChannelItem,
-		clickTrackingParams: Option<String>,
-	},
-	ReplaceChatItemAction {
-		replaceChatItemAction: serde_json::Value,
-		clickTrackingParams: Option<String>,
-	},
-	ShowLiveChatActionPanelAction {
-		showLiveChatActionPanelAction: serde_json::Value,
-		clickTrackingParams: Option<String>,
-	},
-	UpdateLiveChatPollAction {
-		updateLiveChatPollAction: UpdateLiveChatPollAction,
-		clickTrackingParams: Option<String>,
-	},
-	ReplaceLiveChatRendererAction{
-		replaceLiveChatRendererAction: serde_json::Value,
-		clickTrackingParams: Option<String>,
-	},
-    Unknown(serde_json::Value),
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct RemoveChatItem {
-	targetItemId: String,
-}
+	/// listen for extractors running with --live and pass their events on
+	Serve {
+		/// local socket to listen on
+		#[arg(long, default_value = server::SOCKET_NAME)]
+		socket: String,
 
-#[derive(Serialize, Deserialize, Debug)]
-struct RemoveChannelItem {
-	externalChannelId: String,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(untagged)]
-enum ChatItemType {
-	LiveChatMembershipItemRenderer {
-		liveChatMembershipItemRenderer: LiveChatMembershipItemRenderer,
-	},
-	LiveChatPaidMessageRenderer {
-		liveChatPaidMessageRenderer: PaidMessage,
-	},
-	LiveChatPlaceholderItemRenderer {
-		liveChatPlaceholderItemRenderer: serde_json::Value,
-	},
-	LiveChatSponsorshipsGiftPurchaseAnnouncementRenderer {
-		liveChatSponsorshipsGiftPurchaseAnnouncementRenderer: GiftPurchase,
-	},
-	LiveChatSponsorshipsGiftRedemptionAnnouncementRenderer {
-		liveChatSponsorshipsGiftRedemptionAnnouncementRenderer: GiftRedemptionAnnouncement,
-	},
-	LiveChatTextMessageRenderer {
-		liveChatTextMessageRenderer: LiveChatTextMessage,
-	},
-	LiveChatViewerEngagementMessageRenderer {
-		liveChatViewerEngagementMessageRenderer: serde_json::Value,
-	},
-	LiveChatPaidStickerRenderer{
-		liveChatPaidStickerRenderer: PaidSticker,
+		/// append the events of every stream to <dir>/<stream>.jsonl
+		#[arg(long)]
+		#[clap(allow_hyphen_values = true)]
+		output_dir: Option<std::path::PathBuf>,
 	},
-	LiveChatModeChangeMessageRenderer{
-		liveChatModeChangeMessageRenderer: serde_json::Value,
-	},
-	Unknown(serde_json::Value)
-}
 
-#[derive(Serialize, Deserialize, Debug)]
-struct LiveChatTextMessage {
-	authorBadges: Option<Vec<AuthorBadges>>,
-	authorExternalChannelId: String,
-	authorName: SimpleText,
-	authorPhoto: AuthorPhotos,
-	contextMenuAccessibility: serde_json::Value,
-	contextMenuEndpoint: serde_json::Value,
-	id: String,
-	message: RunsContainer,
-	timestampText: Option<SimpleText>,
-	timestampUsec: String,
-	trackingParams: Option<String>,
+	/// keep reading a chat file while yt-dlp is still writing to it
+	Watch(WatchArgs),
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct PaidSticker {
-	authorExternalChannelId: String,
-	authorName: SimpleText,
-	authorNameTextColor: i64,
-	authorPhoto: AuthorPhotos,
-	backgroundColor: i64,
-	contextMenuAccessibility: serde_json::Value,
-	contextMenuEndpoint: serde_json::Value,
-	id: String,
-	moneyChipBackgroundColor: i64,
-	moneyChipTextColor: i64,
-	purchaseAmountText: SimpleText,
-	sticker: PollImage,
-	stickerDisplayHeight: i64,
-	stickerDisplayWidth: i64,
-	timestampText: Option<SimpleText>,
-	timestampUsec: String,
-	trackingParams: Option<String>,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct GiftRedemptionAnnouncement {
-	authorExternalChannelId: String,
-	authorName: SimpleText,
-	authorPhoto: AuthorPhotos,
-	contextMenuAccessibility: serde_json::Value,
-	contextMenuEndpoint: serde_json::Value,
-	id: String,
-	message: RunsContainer,
-	timestampText: Option<SimpleText>,
-	timestampUsec: String,
-	trackingParams: Option<String>,
-}
+// reading the chat file, shared by extract, stats and watch
+#[derive(Args, Debug)]
+struct InputArgs {
+	/// the .live_chat.json yt-dlp wrote, or the .live_chat.json.part it is still writing
+	#[arg(long)]
+	#[clap(allow_hyphen_values = true)]
+	file: String,
 
-#[derive(Serialize, Deserialize, Debug)]
-struct GiftPurchase {
-	authorExternalChannelId: String,
-	header: GiftPurchaseHeader,
-	id: String,
-	timestampUsec: String,
-}
+	/// skip lines that can't be parsed instead of stopping
+	#[arg(long)]
+	skip_invalid: bool,
 
-#[derive(Serialize, Deserialize, Debug)]
-struct GiftPurchaseHeader {
-	liveChatSponsorshipsHeaderRenderer: GiftPurchaseHeaderRenderer,
-}
+	/// write lines that could not be parsed to this file, implies --skip-invalid
+	#[arg(long)]
+	#[clap(allow_hyphen_values = true)]
+	quarantine: Option<String>,
 
-#[derive(Serialize, Deserialize, Debug)]
-struct AuthorBadges {
-	liveChatAuthorBadgeRenderer: LiveChatAuthorBadgeRenderer,
-}
+	/// write unknown youtube payloads grouped by their top level key to this file as jsonl
+	#[arg(long)]
+	#[clap(allow_hyphen_values = true)]
+	drift_report: Option<String>,
 
-#[derive(Serialize, Deserialize, Debug)]
-struct LiveChatAuthorBadgeRenderer {
-	customThumbnail: Option<AuthorPhotos>,
-	accessibility: EmoteAccessability,
-	tooltip: String,
-}
+	/// csv or json file with exchange rates used to convert amounts to --base-currency
+	#[arg(long)]
+	#[clap(allow_hyphen_values = true)]
+	rates: Option<String>,
 
-#[derive(Serialize, Deserialize, Debug)]
-struct GiftPurchaseHeaderRenderer {
-	authorBadges: Option<Vec<AuthorBadges>>,
-	authorName: SimpleText,
-	authorPhoto: AuthorPhotos,
-	contextMenuAccessibility: serde_json::Value,
-	contextMenuEndpoint: serde_json::Value,
-	image: serde_json::Value,
-	primaryText: RunsContainer,
-}
+	/// what --rates converts to and what a bare --min-amount is in
+	#[arg(long, default_value = "USD")]
+	base_currency: String,
 
+	/// how the time of every event is shown and exported
+	#[arg(long, value_enum, default_value_t = timestamps::TimeFormat::Youtube)]
+	time_format: timestamps::TimeFormat,
 
-#[derive(Serialize, Deserialize, Debug)]
-struct LiveChatMembershipItemRenderer {
-	authorBadges: Option<Vec<AuthorBadges>>,
-	authorExternalChannelId: String,
-	authorName: SimpleText,
-	authorPhoto: AuthorPhotos,
-	contextMenuAccessibility: serde_json::Value,
-	contextMenuEndpoint: serde_json::Value,
-	headerPrimaryText: Option<RunsContainer>,
-	headerSubtext: HeaderSubtextType,
-	id: String,
-	message: Option<RunsContainer>,
-	timestampText: Option<SimpleText>,
-	timestampUsec: String,
-	trackingParams: Option<String>,
+	/// utc, local or a fixed offset like +09:00, used by --time-format
+	#[arg(long, default_value = "utc", value_parser = timestamps::parse_timezone)]
+	#[clap(allow_hyphen_values = true)]
+	timezone: timestamps::Timezone,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(untagged)]
-enum HeaderSubtextType {
-	Runs(RunsContainer),
-	SimpleText(SimpleText),
+// these decide what gets printed, written to --outputfile, sent with --live, stored with --sqlite and counted by stats
+#[derive(Args, Debug)]
+struct FilterArgs {
+	/// only keep these event types, can be given more than once or comma separated
+	#[arg(long = "type", value_enum, value_delimiter = ',')]
+	types: Vec<filter::EventType>,
 
-}
+	/// only keep superchats and stickers worth at least this much, like $20, "20 EUR" or 20 in --base-currency,
+	/// converting other currencies with --rates
+	#[arg(long, value_parser = filter::parse_min_amount)]
+	min_amount: Option<filter::MinAmount>,
 
+	/// only keep events from this username, can be given more than once
+	#[arg(long)]
+	#[clap(allow_hyphen_values = true)]
+	author: Vec<String>,
 
-#[derive(Serialize, Deserialize, Debug)]
-struct PaidMessage {
-	authorBadges: Option<Vec<AuthorBadges>>,
-	authorExternalChannelId: String,
-	authorName: SimpleText,
-	authorNameTextColor: i64,
-	authorPhoto: AuthorPhotos,
-	bodyBackgroundColor: i64,
-	bodyTextColor: i64,
-	//contextMenuAccessibility: serde_json::Value,
-	//contextMenuEndpoint: serde_json::Value,
-	headerBackgroundColor: i64,
-	headerTextColor: i64,
-	id: String,
-	message: Option<RunsContainer>,
-	purchaseAmountText: SimpleText,
-	textInputBackgroundColor: i64,
-	timestampText: Option<SimpleText>,
-	timestampColor: i64,
-	timestampUsec: String,
-	//trackingParams: String,
-}
+	/// only keep events from this channel id, can be given more than once
+	#[arg(long)]
+	channel: Vec<String>,
 
-#[derive(Serialize, Deserialize, Debug)]
-struct AuthorPhotos {
-	thumbnails: Vec<Thumbnail>,
-}
+	/// only keep events from this time on, a date like 2023-05-13T17:46:45Z or an offset into the video like 1:23:45
+	#[arg(long, value_parser = filter::parse_time_bound)]
+	#[clap(allow_hyphen_values = true)]
+	since: Option<filter::TimeBound>,
 
-#[derive(Serialize, Deserialize, Debug)]
-struct Thumbnail {
-	height: Option<i64>,
-	url: String,
-	width: Option<i64>
-}
+	/// only keep events up to this time, same format as --since
+	#[arg(long, value_parser = filter::parse_time_bound)]
+	#[clap(allow_hyphen_values = true)]
+	until: Option<filter::TimeBound>,
 
-#[derive(Serialize, Deserialize, Debug)]
-struct AddChatItemAction {
-	item: ChatItemType,
-	clientId: Option<String>,
+	/// only keep events whose message matches this regex
+	#[arg(long, value_parser = regex::Regex::new)]
+	#[clap(allow_hyphen_values = true)]
+	message_regex: Option<regex::Regex>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct UpdateLiveChatPollAction {
-	pollToUpdate: PollToUpdate
-}
+// where the events go, shared by extract and watch
+#[derive(Args, Debug, Default)]
+struct OutputArgs {
+	/// write the events to this file
+	#[arg(long)]
+	#[clap(allow_hyphen_values = true)]
+	outputfile: Option<String>,
 
-#[derive(Serialize, Deserialize, Debug)]
-struct PollToUpdate {
-	pollRenderer: PollRenderer
-}
+	/// format of --outputfile
+	#[arg(long, value_enum, default_value_t = OutputFormat::Jsonl)]
+	format: OutputFormat,
 
-#[derive(Serialize, Deserialize, Debug)]
-struct PollRenderer {
-	choices:Vec<PollChoice>,
-	liveChatPollId:String,
-	header:PollHeader,
-}
+	/// also write normal chat messages to the output file
+	#[arg(long)]
+	export_chat: bool,
 
-#[derive(Serialize, Deserialize, Debug)]
-struct PollHeader {
-	pollHeaderRenderer: PollHeaderRenderer
-}
+	/// also store everything in this sqlite database
+	#[arg(long)]
+	#[clap(allow_hyphen_values = true)]
+	sqlite: Option<String>,
 
-#[derive(Serialize, Deserialize, Debug)]
-struct PollHeaderRenderer {
-	pollQuestion: RunsContainer,
-	//thubnail: PollThumbnail,
-	metadataText: RunsContainer,
-	liveChatPollType: String,
-	//contextMenuButton: PollContextMenuButton,
-}
+	/// push every event to a running serve
+	#[arg(long)]
+	live: bool,
 
+	/// where that serve listens, its --socket
+	#[arg(long, default_value = server::SOCKET_NAME, requires = "live")]
+	socket: String,
 
+	/// don't print the events or the totals
+	#[arg(long)]
+	dontprint: bool,
 
-#[derive(Serialize, Deserialize, Debug)]
-struct RunsContainer {
-	runs: Vec<RunsTypes>,
-	bold: Option<bool>,
-}
+	/// don't print the totals and removals after the events
+	#[arg(long)]
+	no_stats: bool,
 
-#[derive(Serialize, Deserialize, Debug)]
-struct PollChoice {
-	selected:bool,
-	text: RunsContainer,
-	votePercentage: SimpleText,
-	voteRatio:f32,
+	/// also print the top donors by channel after the events
+	#[arg(long, conflicts_with = "no_stats")]
+	top: Option<usize>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(untagged)]
-enum RunsTypes {
-	Text{
-		text:String,
-		italics:Option<bool>,
-		bold:Option<bool>,
-	},
-	Emoji{
-		emoji:PollRunsEmoji
-	},
-	Unknown(serde_json::Value)
-}
+#[derive(Args, Debug)]
+struct ExtractArgs {
+	#[command(flatten)]
+	input: InputArgs,
 
-#[derive(Serialize, Deserialize, Debug)]
-struct PollRunsEmoji {
-	emojiId:String,
-	image:PollImage,
-	searchTerms:Option<Vec<String>>,
-	shortcuts:Option<Vec<String>>,
-	isCustomEmoji:Option<bool>,
-	variantIds:Option<Vec<String>>,
-	supportsSkinTone:Option<bool>,
-}
+	#[command(flatten)]
+	filters: FilterArgs,
 
-#[derive(Serialize, Deserialize, Debug)]
-struct PollImage {
-	accessibility: EmoteAccessability,
-	thumbnails: Vec<Thumbnail>
-}
+	#[command(flatten)]
+	output: OutputArgs,
 
-#[derive(Serialize, Deserialize, Debug)]
-struct EmoteAccessability {
-	accessibilityData: AccessibilityData
-}
+	/// write a single file html report of every paid event
+	#[arg(long)]
+	#[clap(allow_hyphen_values = true)]
+	html: Option<String>,
 
-#[derive(Serialize, Deserialize, Debug)]
-struct AccessibilityData {
-	label: String,
-}
+	/// open a reading queue of superchats, stickers and memberships after processing
+	#[arg(long)]
+	queue: bool,
 
-#[derive(Serialize, Deserialize, Debug)]
-struct SimpleText {
-	simpleText:String
+	/// where read state of the queue is kept, defaults to the chat file with .queue.json added
+	#[arg(long, requires = "queue")]
+	#[clap(allow_hyphen_values = true)]
+	queue_state: Option<String>,
 }
 
+#[derive(Args, Debug)]
+struct StatsArgs {
+	#[command(flatten)]
+	input: InputArgs,
 
+	#[command(flatten)]
+	filters: FilterArgs,
 
+	/// print the top donors by channel
+	#[arg(long)]
+	top: Option<usize>,
 
+	/// write every donor to this file, json if it ends in .json otherwise csv
+	#[arg(long)]
+	#[clap(allow_hyphen_values = true)]
+	leaderboard: Option<String>,
 
+	/// write bans and deleted messages to this file for moderators, json if it ends in .json otherwise csv
+	#[arg(long)]
+	#[clap(allow_hyphen_values = true)]
+	modlog: Option<String>,
 
-
-
-#[derive(Serialize, Deserialize, Debug)]
-struct Sticker {
-	#[serde(rename = "type")]
-	json_type: String,
-	username: String,
-	channel_id: String,
-	sticker_cost: String,
-	sticker_description: String,
-	sticker_image_url: String,
-	time: String,
-	thumbnail_url: String,
-	header_color: i64,
-	body_color: i64,
-}
-#[derive(Serialize, Deserialize, Debug)]
-struct Redemption {
-	#[serde(rename = "type")]
-	json_type: String,
-	thumbnail_url: String,
-	username: String,
-	channel_id: String,
-	time: String,
-	sender: String,
-	header_color: i64,
-	body_color: i64,
-}
-// struct for exporting to json
-#[derive(Serialize, Deserialize, Debug)]
-struct Gift {
-	#[serde(rename = "type")]
-	json_type: String,
-	username: String,
-	channel_id: String,
-	time: String,
-	number: String,
-	header_color: i64,
-	body_color: i64,
-	thumbnail_url: String,
-}
-// struct for exporting to json
-#[derive(Serialize, Deserialize, Debug)]
-struct Membership {
-	#[serde(rename = "type")]
-	json_type: String,
-	username: String,
-	channel_id: String,
-	months: String,
-	message: Option<String>,
-	time: String,
-	header_color: i64,
-	body_color: i64,
-	thumbnail_url: String,
-}
-// struct for exporting to json
-#[derive(Serialize, Deserialize, Debug)]
-struct Donation {
-	#[serde(rename = "type")]
-	json_type: String,
-	username: String,
-	channel_id: String,
-	amount: String,
-	message: Option<String>,
-	time: String,
-	header_color: i64,
-	body_color: i64,
-	thumbnail_url: String,
+	/// only write the files
+	#[arg(long)]
+	dontprint: bool,
 }
 
+#[derive(Args, Debug)]
+struct ReportArgs {
+	/// a jsonl file written by extract --outputfile, from any version
+	#[arg(long)]
+	#[clap(allow_hyphen_values = true)]
+	file: String,
 
+	/// html or markdown
+	#[arg(long, value_enum, default_value_t = ReportFormat::Html)]
+	format: ReportFormat,
 
+	/// where to write the report, printed when not given
+	#[arg(long)]
+	#[clap(allow_hyphen_values = true)]
+	output: Option<String>,
 
+	/// defaults to the stream title in the export, its video id, or the file name
+	#[arg(long)]
+	title: Option<String>,
+}
 
+#[derive(Args, Debug)]
+struct WatchArgs {
+	#[command(flatten)]
+	input: InputArgs,
 
+	#[command(flatten)]
+	filters: FilterArgs,
 
+	#[command(flatten)]
+	output: OutputArgs,
 
-
-#[derive(Serialize, Deserialize, Debug)]
-struct StickerDeserialization {
-	username: String,
-	channel_id: String,
-	sticker_cost: String,
-	sticker_description: String,
-	sticker_image_url: String,
-	time: String,
-	thumbnail_url: String,
-	header_color: i64,
-	body_color: i64,
-}
-#[derive(Serialize, Deserialize, Debug)]
-struct RedemptionDeserialization {
-	thumbnail_url: String,
-	username: String,
-	channel_id: String,
-	time: String,
-	sender: String,
-	header_color: i64,
-	body_color: i64,
-}
-// struct for exporting to json
-#[derive(Serialize, Deserialize, Debug)]
-struct GiftDeserialization {
-	username: String,
-	channel_id: String,
-	time: String,
-	number: String,
-	header_color: i64,
-	body_color: i64,
-	thumbnail_url: String,
+	/// stop after this many seconds without new chat data
+	#[arg(long)]
+	idle_timeout: Option<u64>,
 }
-// struct for exporting to json
-#[derive(Serialize, Deserialize, Debug)]
-struct MembershipDeserialization {
-	username: String,
-	channel_id: String,
-	months: String,
-	message: Option<String>,
-	time: String,
-	header_color: i64,
-	body_color: i64,
-	thumbnail_url: String,
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+enum OutputFormat {
+	#[default]
+	Jsonl,
+	Csv,
+	Tsv,
 }
-// struct for exporting to json
-#[derive(Serialize, Deserialize, Debug)]
-struct DonationDeserialization {
-	username: String,
-	channel_id: String,
-	amount: String,
-	message: Option<String>,
-	time: String,
-	header_color: i64,
-	body_color: i64,
-	thumbnail_url: String,
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ReportFormat {
+	Html,
+	Markdown,
 }
 
+// what one run over a chat file does, put together from the extract, stats or watch arguments
+struct Run {
+	input: InputArgs,
+	filters: FilterArgs,
+	output: OutputArgs,
+	follow: bool,
+	follow_timeout: Option<u64>,
+	print_events: bool,
+	print_stats: bool,
+	top: Option<usize>,
+	leaderboard: Option<String>,
+	modlog: Option<String>,
+	html: Option<String>,
+	queue: bool,
+	queue_state: Option<String>,
+}
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(tag = "type")]
-enum ExportStructs {
-	Donation(DonationDeserialization),
-	Membership(MembershipDeserialization),
-	GiftMembership(RedemptionDeserialization),
-	GiftingMembership(GiftDeserialization),
-	Sticker(StickerDeserialization),
+fn main() {
+	let args = match Cli::parse().command {
+		Command::Extract(extract) => Run {
+			print_events: !extract.output.dontprint,
+			print_stats: !extract.output.dontprint && !extract.output.no_stats,
+			top: extract.output.top,
+			input: extract.input,
+			filters: extract.filters,
+			output: extract.output,
+			follow: false,
+			follow_timeout: None,
+			leaderboard: None,
+			modlog: None,
+			html: extract.html,
+			queue: extract.queue,
+			queue_state: extract.queue_state,
+		},
+		Command::Stats(stats) => Run {
+			input: stats.input,
+			filters: stats.filters,
+			output: OutputArgs::default(),
+			follow: false,
+			follow_timeout: None,
+			print_events: false,
+			print_stats: !stats.dontprint,
+			top: stats.top,
+			leaderboard: stats.leaderboard,
+			modlog: stats.modlog,
+			html: None,
+			queue: false,
+			queue_state: None,
+		},
+		Command::Watch(watch) => Run {
+			print_events: !watch.output.dontprint,
+			print_stats: !watch.output.dontprint && !watch.output.no_stats,
+			top: watch.output.top,
+			input: watch.input,
+			filters: watch.filters,
+			output: watch.output,
+			follow: true,
+			follow_timeout: watch.idle_timeout,
+			leaderboard: None,
+			modlog: None,
+			html: None,
+			queue: false,
+			queue_state: None,
+		},
+		Command::Report(report) => {
+			write_report(report);
+			return;
+		},
+		Command::Serve { socket, output_dir } => {
+			server::run(server::Options { socket, output_dir }).expect("server failed");
+			return;
+		},
+	};
+	run(args);
 }
 
+fn run(args: Run) {
+	let file_path = args.input.file.clone();
 
-fn main() {
-    // get file from cli using clap
-    let args = Cli::parse();
-    let file = std::fs::read_to_string(&args.file).expect("could not read file");
+    // read the file through a buffer so huge chat logs don't have to fit in memory
+    let follow_timeout = args.follow_timeout.map(std::time::Duration::from_secs);
+    let mut lines = follow::ChatLines::open(&file_path, args.follow, follow_timeout).expect("could not open file");
 	
 	let mut num_superchats = 0;
 	let mut num_messages = 0;
@@ -546,657 +372,653 @@ fn main() {
 	let mut num_stickers = 0;
 
 	let mut donations = Vec::new();
+
+	let rate_table = args.input.rates.as_ref().map(|path| rates::RateTable::load(path, &args.input.base_currency).expect("could not load rate table"));
+	let filter = filter::Filter {
+		types: args.filters.types.clone(),
+		min_amount: args.filters.min_amount.clone(),
+		base_currency: args.input.base_currency.clone(),
+		authors: args.filters.author.clone(),
+		channels: args.filters.channel.clone(),
+		since: args.filters.since,
+		until: args.filters.until,
+		message_regex: args.filters.message_regex.clone(),
+	};
+	// totals in minor units of the base currency, only used with --rates
+	let mut converted_superchats = 0;
+	let mut converted_stickers = 0;
+	let mut num_unconverted = 0;
+	// extract and watch print events while reading, stats only prints the summary once the file is read
+	let mut print = args.print_events;
 	macro_rules! println {
 		($($rest:tt)*) => {
-			if !args.dontprint {
+			if print {
 				std::println!($($rest)*)
 			}
 		}
 	}
 
+	// what the removal summary and --modlog need from a chat message
+	// youtube caps chat at 200 characters, the author is an index into chatters so names aren't kept per message
 	struct TextChat {
 		message: String,
-		external_channel_id: String,
+		chatter: usize,
 		id: String,
+		time: String,
+	}
+
+	// video id and title from the file name and the .info.json yt-dlp writes next to it
+	let stream_name = metadata::stream_name(&file_path);
+	let stream_metadata = metadata::StreamMetadata::load(&file_path);
+
+	let time_formatter = timestamps::TimeFormatter { format: args.input.time_format, timezone: args.input.timezone };
+
+	// events are pushed to the server as soon as they are parsed
+	let mut live = args.output.live.then(|| live::LiveClient::new(&args.output.socket, stream_name.clone(), stream_metadata.clone()));
+
+	let mut archive = args.output.sqlite.as_ref().map(|path| {
+		sqlite::Archive::open(path, &stream_name, &file_path, &stream_metadata).expect("could not open sqlite database")
+	});
+
+	// only the outputs written once the file is read need every event kept until then
+	let keep_events = args.output.outputfile.is_some() || args.html.is_some() || args.queue || args.modlog.is_some();
+	// the removal summary lists the paid events of wiped channels, those are few enough to keep
+	let keep_paid_events = keep_events || args.print_stats;
+	let mut leaderboard = (args.top.is_some() || args.leaderboard.is_some()).then(leaderboard::Leaderboard::default);
+
+	// keep an event for the output file, archive it under its youtube id and push it to the server
+	macro_rules! export {
+		($event:expr) => {
+			let event: ExportStructs = $event;
+			if let Some(archive) = archive.as_mut() {
+				archive.insert_event(event.id(), &event).expect("failed to write to sqlite database");
+			}
+			if let Some(leaderboard) = leaderboard.as_mut() {
+				leaderboard.add(&event);
+			}
+			if let Some(live) = live.as_mut() {
+				live.send(event.clone());
+			}
+			if keep_events || (keep_paid_events && !matches!(event, ExportStructs::TextMessage(_))) {
+				donations.push(event);
+			}
+		}
+	}
+
+	// custom emoji images by their shortcut, used to put them back into the html report
+	let mut emoji_images: std::collections::BTreeMap<String,String> = std::collections::BTreeMap::new();
+	// the text of a message, remembering its custom emojis and printing runs the library doesn't know
+	macro_rules! message_text {
+		($message:expr) => {{
+			let message: event::Message = $message;
+			for emoji in message.emojis.iter() {
+				if let Some(image_url) = &emoji.image_url {
+					emoji_images.entry(emoji.label.clone()).or_insert_with(|| image_url.clone());
+				}
+			}
+			for unknown in message.unknown_runs.iter() {
+				println!("UNKNOWN VALUE IN RUNS: {:#?}", unknown);
+			}
+			message.text
+		}}
 	}
 
+	// chat is only kept when something shows removed chat, so extracting a huge file doesn't grow with its chat
+	// that is --modlog and the summary of the stats command, extract and watch only count removed chat
+	let keep_chat = args.modlog.is_some() || (args.print_stats && !args.print_events);
 	let mut messages:Vec<TextChat> = Vec::new();
-	let mut removed_channels:Vec<(String,Vec<ExportStructs>,Vec<String>)> = Vec::new();
-	let mut removed_messages:Vec<String> = Vec::new();
+	// channel id and latest name of everyone who chatted
+	let mut chatters:Vec<(String,String)> = Vec::new();
+	let mut chatter_index: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+	// the offsets into the video are when the removal happened
+	// channel id, their paid events and message ids up to the wipe, offset of the wipe
+	type RemovedChannel = (String, Vec<ExportStructs>, Vec<String>, Option<i64>);
+	let mut removed_channels:Vec<RemovedChannel> = Vec::new();
+	let mut removed_messages:Vec<(String,Option<i64>)> = Vec::new();
+	// chat item id -> why it was removed, put into the export once the whole file is read
+	let mut removed_ids: std::collections::HashMap<String, &str> = std::collections::HashMap::new();
+
+	// lines that failed to parse, with their line number and the error
+	let skip_invalid = args.input.skip_invalid || args.input.quarantine.is_some();
+	let quarantine = args.input.quarantine.as_ref().map(|path| {
+		let file = std::fs::File::create(path).expect("failed to create quarantine file");
+		std::io::BufWriter::new(file)
+	});
+	let mut invalid_lines = quarantine::InvalidLines::new(quarantine);
+
+	// payloads from youtube that none of the structs above match
+	let mut drift = drift::DriftReport::default();
 
 	// iterate over every line in the file
-	for line in file.lines() {
+	let mut line = String::new();
+	let mut line_number = 0;
+	loop {
+		match lines.next_line(&mut line).expect("could not read line") {
+			follow::Next::Line => {},
+			// still waiting on yt-dlp to write more
+			follow::Next::Idle => {
+				// good time to try getting buffered events to the server again
+				if let Some(live) = live.as_mut() {
+					live.heartbeat();
+				}
+				// and to commit what the archive has so far
+				if let Some(archive) = archive.as_mut() {
+					archive.checkpoint().expect("failed to write to sqlite database");
+				}
+				continue;
+			},
+			follow::Next::End => break,
+		}
+		let line = line.as_str();
+		line_number += 1;
 		// parse as json value
 		//let json: serde_json::Value = serde_json::from_str(line).expect("could not parse line");
 		//println!("json: {json:?}");
 
-		// parse the line as chat item
-		let chat_item: ChatItem = serde_json::from_str(line).expect("could not parse line");
-		for action in chat_item.replayChatItemAction.actions {
-			match action {
-				Action::AddChatItemAction { addChatItemAction, .. } => {
-					//println!("addChatItemAction: {:#?}", addChatItemAction);
-					// adds things in the chat like messages, donations, join button, etc.
-					match addChatItemAction.item {
-						ChatItemType::LiveChatPaidMessageRenderer { liveChatPaidMessageRenderer } => {
-							
-							// donation
-							num_superchats += 1;
-							//continue;
-				
-
-							// liveChatPaidMessageRenderer.bodyBackgroundColor is the raw decimal value of the color
-							// convert it to hex
-							let background_color = format!("{:x}", liveChatPaidMessageRenderer.bodyBackgroundColor);
-							// background_color is in argb format with 2 digits for each part
-							// split into alpha, red, green, blue
-							// this should be safe because the alpha is always ff
-							
-							assert!(background_color.len() == 8, "background color is not 8 characters long: {background_color}");
-
-							// split into alpha, red, green, blue u8 values
-							//let alpha = u8::from_str_radix(&background_color[0..2], 16).expect("could not parse alpha");
-							let red = u8::from_str_radix(&background_color[2..4], 16).expect("could not parse red");
-							let green = u8::from_str_radix(&background_color[4..6], 16).expect("could not parse green");
-							let blue = u8::from_str_radix(&background_color[6..8], 16).expect("could not parse blue");
-							
-							let sep = "==========donation start==========".black().on_truecolor(red, green, blue);
-							println!("{}",sep);
-							let timestring = if let Some(timestamp) = liveChatPaidMessageRenderer.timestampText {
-								// should always exist in replays
-								timestamp.simpleText
-							} else {
-								use chrono::NaiveDateTime;
-								let timestamp = liveChatPaidMessageRenderer.timestampUsec;
-								let timestamp = timestamp.parse::<i64>().expect("could not parse timestamp");
-								let timestamp = timestamp / 1_000_000;
-								let timestamp = NaiveDateTime::from_timestamp_opt(timestamp, 0).expect("could not convert timestamp to datetime");
-								timestamp.format("%Y-%m-%d %H:%M:%S").to_string()
-							};
-
-							println!("time: {}", timestring);
-							//println!("donation: {:#?}", liveChatPaidMessageRenderer);
-							// print username and channel id
-							println!("username: {}, channel: https://youtube.com/channel/{}", liveChatPaidMessageRenderer.authorName.simpleText, liveChatPaidMessageRenderer.authorExternalChannelId);
-							// print amount
-							println!("amount: {}", liveChatPaidMessageRenderer.purchaseAmountText.simpleText);
-
-							// print background color
-							//println!("background color: {}", background_color);
-
-							// print message
-							let mut message = String::new();
-							if let Some(message_run) = liveChatPaidMessageRenderer.message {
-								for run in message_run.runs {
-									match run {
-										RunsTypes::Text { text, .. } => {
-											message.push_str(&text);
-										},
-										RunsTypes::Emoji { emoji } => {
-											if let Some(is_custom_emoji) = emoji.isCustomEmoji {
-												if is_custom_emoji {
-													message.push_str(format!(":{}:",&emoji.image.accessibility.accessibilityData.label).as_str());
-												} else {
-													message.push_str(&emoji.emojiId);
-												}
-											} else {
-												message.push_str(&emoji.emojiId);
-											}
-	
-											//poll_name.push_str(&emoji.image.accessibility.accessibilityData.label);
-										},
-										RunsTypes::Unknown(unknown) => {
-											println!("UNKNOWN VALUE IN RUNS: {:#?}", unknown);
-										},
-									}
-								}
-								println!("message: {}", message);
-							}
-							
-
-							// if message length is 0, set it to None
-							let message = if message.is_empty() {
-								None
-							} else {
-								Some(message)
-							};
-
-							// thumbnail_url is the last url in the thumbnails array
-							// create donation struct
-							let donation = Donation {
-								json_type: "Donation".to_string(),
-								thumbnail_url: liveChatPaidMessageRenderer.authorPhoto.thumbnails.last().expect("could not get thumbnail url").url.clone(),
-								username: liveChatPaidMessageRenderer.authorName.simpleText.clone(),
-								channel_id: liveChatPaidMessageRenderer.authorExternalChannelId.clone(),
-								amount: liveChatPaidMessageRenderer.purchaseAmountText.simpleText.clone(),
-								message,
-								time: timestring.clone(),
-								header_color: liveChatPaidMessageRenderer.headerBackgroundColor,
-								body_color: liveChatPaidMessageRenderer.bodyBackgroundColor,
-							};
-							donations.push(serde_json::to_string(&donation).expect("could not serialize donation"));
-
-							println!("===========donation end===========");
+		// parse the line into events
+		let events = match superchat_extractor::parse_line(line) {
+			Ok(events) => events,
+			Err(e) => {
+				if !skip_invalid {
+					panic!("could not parse line {line_number}: {e}");
+				}
+				invalid_lines.record(line_number, line, &e).expect("failed to write to quarantine file");
+				continue;
+			}
+		};
+		let event_times = |time: &event::EventTime| {
+			(timestamps::rfc3339_from_usec(time.timestamp_usec), timestamps::video_offset_ms(time.video_offset_ms, time.timestamp_usec, stream_metadata.release_timestamp))
+		};
+		for event in events {
+			let shown = filter.matches(&event, rate_table.as_ref(), stream_metadata.release_timestamp);
+			// text messages and removals are tracked even when filtered out, later removals need them
+			if !shown && !matches!(event, ChatEvent::TextMessage(_) | ChatEvent::MessageDeleted(_) | ChatEvent::AuthorRemoved(_)) {
+				continue;
+			}
+			match event {
+				ChatEvent::Superchat(superchat) => {
+					
+					// donation
+					num_superchats += 1;
+					//continue;
+		
+
+					// bodyBackgroundColor is the raw decimal value of the color
+					// convert it to hex
+					let background_color = format!("{:x}", superchat.body_color);
+					// background_color is in argb format with 2 digits for each part
+					// split into alpha, red, green, blue
+					// this should be safe because the alpha is always ff
+					
+					assert!(background_color.len() == 8, "background color is not 8 characters long: {background_color}");
 
-						},
-						ChatItemType::LiveChatMembershipItemRenderer { liveChatMembershipItemRenderer } => {
-
-
-							num_memberships+=1;
-							// join button
-							let sep = "=========membership start=========".black().on_truecolor(10, 128, 67);
-							println!("{}",sep);
-							let timestring = if let Some(timestamp) = liveChatMembershipItemRenderer.timestampText {
-								// should always exist in replays
-								timestamp.simpleText
-							} else {
-								use chrono::NaiveDateTime;
-								let timestamp = liveChatMembershipItemRenderer.timestampUsec;
-								let timestamp = timestamp.parse::<i64>().expect("could not parse timestamp");
-								let timestamp = timestamp / 1_000_000;
-								let timestamp = NaiveDateTime::from_timestamp_opt(timestamp, 0).expect("could not convert timestamp to datetime");
-								timestamp.format("%Y-%m-%d %H:%M:%S").to_string()
-							};
-							println!("time: {}", timestring);
-							//println!("membership: {:#?}", liveChatMembershipItemRenderer);
-							// print username and channel id
-							println!("username: {}, channel: https://youtube.com/channel/{}", liveChatMembershipItemRenderer.authorName.simpleText, liveChatMembershipItemRenderer.authorExternalChannelId);
-							// print number of months
-							
-							// if the user just joined the channel then headerPrimaryText will be None
-							// and the welcome message will be in the headerSubtext field
-							// so check if headerPrimaryText is None and if it is then say 0 months
-							// otherwise the number of months is in headerPrimaryText
-
-							let months = if let Some(headerPrimaryText) = liveChatMembershipItemRenderer.headerPrimaryText {
-								let mut message = String::new();
-								for runs in headerPrimaryText.runs {
-									match runs {
-										RunsTypes::Text { text, .. } => {
-											message.push_str(&text);
-										},
-										RunsTypes::Emoji { emoji } => {
-											if let Some(is_custom_emoji) = emoji.isCustomEmoji {
-												if is_custom_emoji {
-													message.push_str(format!(":{}:",&emoji.image.accessibility.accessibilityData.label).as_str());
-												} else {
-													message.push_str(&emoji.emojiId);
-												}
-											} else {
-												message.push_str(&emoji.emojiId);
-											}
-	
-											//poll_name.push_str(&emoji.image.accessibility.accessibilityData.label);
-										},
-										RunsTypes::Unknown(unknown) => {
-											println!("UNKNOWN VALUE IN RUNS: {:#?}", unknown);
-										},
-									}
-								}
-								println!("months: {}", message);
-								message
-							} else {
-								println!("months: New member.");
-								"New member.".to_string()
-							};
-
-							// print message if there is one
-							let mut message = String::new();
-							if let Some(lmessage) = liveChatMembershipItemRenderer.message {
-								for run in lmessage.runs {
-									match run {
-										RunsTypes::Text { text, .. } => {
-											message.push_str(&text);
-										},
-										RunsTypes::Emoji { emoji } => {
-											if let Some(is_custom_emoji) = emoji.isCustomEmoji {
-												if is_custom_emoji {
-													message.push_str(format!(":{}:",&emoji.image.accessibility.accessibilityData.label).as_str());
-												} else {
-													message.push_str(&emoji.emojiId);
-												}
-											} else {
-												message.push_str(&emoji.emojiId);
-											}
-	
-											//poll_name.push_str(&emoji.image.accessibility.accessibilityData.label);
-										},
-										RunsTypes::Unknown(unknown) => {
-											println!("UNKNOWN VALUE IN RUNS: {:#?}", unknown);
-										},
-									}
-								}
-							}
+					// split into alpha, red, green, blue u8 values
+					//let alpha = u8::from_str_radix(&background_color[0..2], 16).expect("could not parse alpha");
+					let red = u8::from_str_radix(&background_color[2..4], 16).expect("could not parse red");
+					let green = u8::from_str_radix(&background_color[4..6], 16).expect("could not parse green");
+					let blue = u8::from_str_radix(&background_color[6..8], 16).expect("could not parse blue");
+					
+					let sep = "==========donation start==========".black().on_truecolor(red, green, blue);
+					println!("{}",sep);
+					let event_date = rates::date_from_usec(superchat.time.timestamp_usec);
+					let (utc_timestamp, video_offset_ms) = event_times(&superchat.time);
+					let timestring = time_formatter.format(superchat.time.timestamp_text, superchat.time.timestamp_usec, video_offset_ms);
+
+					println!("time: {}", timestring);
+					// print username and channel id
+					println!("username: {}, channel: https://youtube.com/channel/{}", superchat.author.name, superchat.author.channel_id);
+					// print amount
+					println!("amount: {}", superchat.amount_text);
+
+					// print message
+					let mut message = String::new();
+					if let Some(superchat_message) = superchat.message {
+						message = message_text!(superchat_message);
+						println!("message: {}", message);
+					}
 
-							// if message length is 0, set it to None
-							let message = if message.is_empty() {
-								None
-							} else {
-								Some(message)
-							};
-
-							// thumbnail_url is the last url in the thumbnails array
-							// create donation struct
-							let membership = Membership {
-								json_type: "Membership".to_string(),
-								thumbnail_url: liveChatMembershipItemRenderer.authorPhoto.thumbnails.last().expect("could not get thumbnail url").url.clone(),
-								username: liveChatMembershipItemRenderer.authorName.simpleText.clone(),
-								channel_id: liveChatMembershipItemRenderer.authorExternalChannelId.clone(),
-								months,
-								message,
-								time: timestring.clone(),
-								header_color: i64::from_str_radix("0a8043", 16).expect("somhow failed to parse sponsor color"),
-								body_color: i64::from_str_radix("0f9d58", 16).expect("somhow failed to parse sponsor color"),
-							};
-							donations.push(serde_json::to_string(&membership).expect("could not serialize donation"));
-
-							println!("==========membership end==========");
-						},
-						ChatItemType::LiveChatSponsorshipsGiftPurchaseAnnouncementRenderer { liveChatSponsorshipsGiftPurchaseAnnouncementRenderer } => {
-
-							num_gifts+=1;
-							// gift purchase
-							let sep = "=========gifting memberships start=========".black().on_truecolor(15, 157, 88);
-							println!("{}",sep);
-							//println!("membership gift send: {:#?}", liveChatSponsorshipsGiftPurchaseAnnouncementRenderer);
-							
-							// print username and channel id
-							println!("username: {}, channel: https://youtube.com/channel/{}", liveChatSponsorshipsGiftPurchaseAnnouncementRenderer.header.liveChatSponsorshipsHeaderRenderer.authorName.simpleText, liveChatSponsorshipsGiftPurchaseAnnouncementRenderer.authorExternalChannelId);
-							// print number of gifted memberships
-							let number_position = liveChatSponsorshipsGiftPurchaseAnnouncementRenderer.header.liveChatSponsorshipsHeaderRenderer.primaryText.runs.get(1);
-							let mut num_gifted = String::new();
-							if let Some(e) = number_position {
-								match e {
-									RunsTypes::Text { text, .. } => {
-										num_gifted = text.clone();
-										println!("gifted memberships: {}", text);
-									},
-									_ => {
-										println!("error getting number of gifted memberships");
-									}
-								}
-							};
-
-
-							let timestring = {
-								use chrono::NaiveDateTime;
-								let timestamp = liveChatSponsorshipsGiftPurchaseAnnouncementRenderer.timestampUsec;
-								let timestamp = timestamp.parse::<i64>().expect("could not parse timestamp");
-								let timestamp = timestamp / 1_000_000;
-								let timestamp = NaiveDateTime::from_timestamp_opt(timestamp, 0).expect("could not convert timestamp to datetime");
-								timestamp.format("%Y-%m-%d %H:%M:%S").to_string()
-							};
-
-							// thumbnail_url is the last url in the thumbnails array
-							// create gifting donation struct
-							let gift = Gift {
-								json_type: "GiftingMembership".to_string(),
-								time: timestring.clone(),
-								thumbnail_url: liveChatSponsorshipsGiftPurchaseAnnouncementRenderer.header.liveChatSponsorshipsHeaderRenderer.authorPhoto.thumbnails.last().expect("could not get thumbnail url").url.clone(),
-								username: liveChatSponsorshipsGiftPurchaseAnnouncementRenderer.header.liveChatSponsorshipsHeaderRenderer.authorName.simpleText.clone(),
-								channel_id: liveChatSponsorshipsGiftPurchaseAnnouncementRenderer.authorExternalChannelId.clone(),
-								number: num_gifted,
-								header_color: i64::from_str_radix("0a8043", 16).expect("somhow failed to parse sponsor color"),
-								body_color: i64::from_str_radix("0f9d58", 16).expect("somhow failed to parse sponsor color"),
-							};
-
-							donations.push(serde_json::to_string(&gift).expect("could not serialize donation"));
-
-
-							println!("==========gifting memberships end==========");
+					// if message length is 0, set it to None
+					let message = if message.is_empty() {
+						None
+					} else {
+						Some(message)
+					};
+
+					let parsed_amount = superchat.amount;
+					let converted_value = match (&rate_table, &parsed_amount) {
+						(Some(rate_table), Some(amount)) => rate_table.convert(amount, event_date),
+						_ => None,
+					};
+					if rate_table.is_some() {
+						match &converted_value {
+							Some(value) => converted_superchats += value.minor_units,
+							None => num_unconverted += 1,
+						}
+					}
 
-						},
-						ChatItemType::LiveChatSponsorshipsGiftRedemptionAnnouncementRenderer { liveChatSponsorshipsGiftRedemptionAnnouncementRenderer } => {
-							num_redemptions+=1;
-							// message about person who got a gift
-							//println!("membership gift receive: {:#?}", liveChatSponsorshipsGiftRedemptionAnnouncementRenderer);
-
-							let sep = "=========membership redemption begins=========".black().on_truecolor(15, 157, 88);
-							println!("{}",sep);
-
-							let timestring = if let Some(timestamp) = liveChatSponsorshipsGiftRedemptionAnnouncementRenderer.timestampText {
-								// should always exist in replays
-								timestamp.simpleText
-							} else {
-								use chrono::NaiveDateTime;
-								let timestamp = liveChatSponsorshipsGiftRedemptionAnnouncementRenderer.timestampUsec;
-								let timestamp = timestamp.parse::<i64>().expect("could not parse timestamp");
-								let timestamp = timestamp / 1_000_000;
-								let timestamp = NaiveDateTime::from_timestamp_opt(timestamp, 0).expect("could not convert timestamp to datetime");
-								timestamp.format("%Y-%m-%d %H:%M:%S").to_string()
-							};
-							println!("time: {}", timestring);
-
-							// print username and message
-							let mut message = String::new();
-							for part in &liveChatSponsorshipsGiftRedemptionAnnouncementRenderer.message.runs {
-								match part {
-									RunsTypes::Text { text, .. } => {
-										message.push_str(text);
-									},
-									RunsTypes::Emoji { emoji } => {
-										if let Some(is_custom_emoji) = emoji.isCustomEmoji {
-											if is_custom_emoji {
-												message.push_str(format!(":{}:",&emoji.image.accessibility.accessibilityData.label).as_str());
-											} else {
-												message.push_str(&emoji.emojiId);
-											}
-										} else {
-											message.push_str(&emoji.emojiId);
-										}
-
-										//poll_name.push_str(&emoji.image.accessibility.accessibilityData.label);
-									},
-									RunsTypes::Unknown(unknown) => {
-										println!("UNKNOWN VALUE IN RUNS: {:#?}", unknown);
-									},
-								}
-							}
+					// create donation struct
+					let donation = export::Donation {
+						id: superchat.id.clone(),
+						thumbnail_url: superchat.author.photo_url.expect("could not get thumbnail url"),
+						username: superchat.author.name,
+						channel_id: superchat.author.channel_id,
+						amount: superchat.amount_text,
+						currency: parsed_amount.as_ref().map(|amount| amount.currency.clone()),
+						amount_value: parsed_amount.as_ref().map(Amount::value),
+						converted_value: converted_value.as_ref().map(Amount::value),
+						base_currency: rate_table.as_ref().map(|rate_table| rate_table.base.clone()),
+						message,
+						time: timestring.clone(),
+						video_id: stream_metadata.video_id.clone(),
+						title: stream_metadata.title.clone(),
+						channel: stream_metadata.channel.clone(),
+						release_timestamp: stream_metadata.release_timestamp,
+						timestamp: utc_timestamp,
+						video_offset_ms,
+						removed: false,
+						removed_reason: None,
+						header_color: superchat.header_color,
+						body_color: superchat.body_color,
+					};
+					export!(ExportStructs::Donation(donation));
+
+					println!("===========donation end===========");
 
-							let mut sender = String::from("unknown");
-							if let Some(RunsTypes::Text { text, .. }) = liveChatSponsorshipsGiftRedemptionAnnouncementRenderer.message.runs.into_iter().nth(1) {
-								sender = text;
-							}
+				},
+				ChatEvent::Membership(membership) => {
+
+
+					num_memberships+=1;
+					// join button
+					let sep = "=========membership start=========".black().on_truecolor(10, 128, 67);
+					println!("{}",sep);
+					let (utc_timestamp, video_offset_ms) = event_times(&membership.time);
+					let timestring = time_formatter.format(membership.time.timestamp_text, membership.time.timestamp_usec, video_offset_ms);
+					println!("time: {}", timestring);
+					// print username and channel id
+					println!("username: {}, channel: https://youtube.com/channel/{}", membership.author.name, membership.author.channel_id);
+					// print number of months
+					
+					// if the user just joined the channel there are no months
+					// and the welcome message is all youtube sends
+					let months = if let Some(months) = membership.months {
+						let months = message_text!(months);
+						println!("months: {}", months);
+						months
+					} else {
+						println!("months: New member.");
+						"New member.".to_string()
+					};
+
+					// message if there is one
+					let mut message = String::new();
+					if let Some(membership_message) = membership.message {
+						message = message_text!(membership_message);
+					}
 
-							// create gifting donation struct
-							let redemption = Redemption {
-								json_type: "GiftMembership".to_string(),
-								thumbnail_url: liveChatSponsorshipsGiftRedemptionAnnouncementRenderer.authorPhoto.thumbnails.last().expect("could not get thumbnail url").url.clone(),
-								username: liveChatSponsorshipsGiftRedemptionAnnouncementRenderer.authorName.simpleText.clone(),
-								channel_id: liveChatSponsorshipsGiftRedemptionAnnouncementRenderer.authorExternalChannelId.clone(),
-								sender,
-								time: timestring.clone(),
-								header_color: i64::from_str_radix("0a8043", 16).expect("somhow failed to parse sponsor color"),
-								body_color: i64::from_str_radix("0f9d58", 16).expect("somhow failed to parse sponsor color"),
-							};
-
-							donations.push(serde_json::to_string(&redemption).expect("could not serialize donation"));
-
-							println!("{} {}", liveChatSponsorshipsGiftRedemptionAnnouncementRenderer.authorName.simpleText, message);
-							// print recipient channel link
-							println!("channel: https://youtube.com/channel/{}", liveChatSponsorshipsGiftRedemptionAnnouncementRenderer.authorExternalChannelId);
-							println!("==========membership redemption ends==========");
-						},
-						ChatItemType::LiveChatPaidStickerRenderer { liveChatPaidStickerRenderer } => {
-
-							num_stickers+=1;
-							// liveChatPaidMessageRenderer.bodyBackgroundColor is the raw decimal value of the color
-							// convert it to hex
-							let background_color = format!("{:x}", liveChatPaidStickerRenderer.backgroundColor);
-							// background_color is in argb format with 2 digits for each part
-							// split into alpha, red, green, blue
-							// this should be safe because the alpha is always ff
-							
-							assert!(background_color.len() == 8, "background color is not 8 characters long: {background_color}");
-
-							// split into alpha, red, green, blue u8 values
-							let alpha = u8::from_str_radix(&background_color[0..2], 16).expect("could not parse alpha");
-							let red = u8::from_str_radix(&background_color[2..4], 16).expect("could not parse red");
-							let green = u8::from_str_radix(&background_color[4..6], 16).expect("could not parse green");
-							let blue = u8::from_str_radix(&background_color[6..8], 16).expect("could not parse blue");
-
-							let sep = "=========sticker start=========".black().on_truecolor(red, green, blue);
-							println!("{}",sep);
-
-							let timestring = if let Some(timestamp) = liveChatPaidStickerRenderer.timestampText {
-								// should always exist in replays
-								timestamp.simpleText
-							} else {
-								use chrono::NaiveDateTime;
-								let timestamp = liveChatPaidStickerRenderer.timestampUsec;
-								let timestamp = timestamp.parse::<i64>().expect("could not parse timestamp");
-								let timestamp = timestamp / 1_000_000;
-								let timestamp = NaiveDateTime::from_timestamp_opt(timestamp, 0).expect("could not convert timestamp to datetime");
-								timestamp.format("%Y-%m-%d %H:%M:%S").to_string()
-							};
-
-								// if it doesn't exist fallback to .timestampUsec
-								// and convert from microseconds to datetime
-							println!("time: {}", timestring);
-
-							// sticker
-							//println!("sticker: {:#?}", liveChatPaidStickerRenderer);
-
-							// print username and channel link
-							println!("username: {}, channel: https://youtube.com/channel/{}", liveChatPaidStickerRenderer.authorName.simpleText, liveChatPaidStickerRenderer.authorExternalChannelId);
-							// print sticker cost
-							println!("sticker cost: {}", liveChatPaidStickerRenderer.purchaseAmountText.simpleText);
-							// print sticker description
-							println!("sticker description: {}", liveChatPaidStickerRenderer.sticker.accessibility.accessibilityData.label);
-
-							// create gifting donation struct
-							let donation = Sticker {
-								json_type: "Sticker".to_string(),
-								time: timestring.clone(),
-								username: liveChatPaidStickerRenderer.authorName.simpleText.clone(),
-								channel_id: liveChatPaidStickerRenderer.authorExternalChannelId.clone(),
-								sticker_cost: liveChatPaidStickerRenderer.purchaseAmountText.simpleText.clone(),
-								sticker_image_url: liveChatPaidStickerRenderer.sticker.thumbnails.last().expect("could not get thumbnail url").url.clone(),
-								sticker_description: liveChatPaidStickerRenderer.sticker.accessibility.accessibilityData.label.clone(),
-								thumbnail_url: liveChatPaidStickerRenderer.authorPhoto.thumbnails.last().expect("could not get thumbnail url").url.clone(),
-								header_color: i64::from_str_radix(&background_color, 16).expect("somhow failed to parse sponsor color"),
-								body_color: i64::from_str_radix(&background_color, 16).expect("somhow failed to parse sponsor color"),
-							};
-
-							donations.push(serde_json::to_string(&donation).expect("could not serialize donation"));
-
-							println!("==========sticker end==========");
-						},
+					// if message length is 0, set it to None
+					let message = if message.is_empty() {
+						None
+					} else {
+						Some(message)
+					};
+
+					// create donation struct
+					let membership_export = export::Membership {
+						id: membership.id.clone(),
+						thumbnail_url: membership.author.photo_url.expect("could not get thumbnail url"),
+						username: membership.author.name,
+						channel_id: membership.author.channel_id,
+						months,
+						message,
+						time: timestring.clone(),
+						video_id: stream_metadata.video_id.clone(),
+						title: stream_metadata.title.clone(),
+						channel: stream_metadata.channel.clone(),
+						release_timestamp: stream_metadata.release_timestamp,
+						timestamp: utc_timestamp,
+						video_offset_ms,
+						removed: false,
+						removed_reason: None,
+						header_color: i64::from_str_radix("0a8043", 16).expect("somhow failed to parse sponsor color"),
+						body_color: i64::from_str_radix("0f9d58", 16).expect("somhow failed to parse sponsor color"),
+					};
+					export!(ExportStructs::Membership(membership_export));
+
+					println!("==========membership end==========");
+				},
+				ChatEvent::GiftPurchase(gift_purchase) => {
 
-						ChatItemType::LiveChatTextMessageRenderer { liveChatTextMessageRenderer } => {
-							// normal message
-
-							// store text and id
-							let mut message = String::new();
-							for run in liveChatTextMessageRenderer.message.runs {
-								match run {
-									RunsTypes::Text { text, .. } => {
-										message.push_str(&text);
-									},
-									RunsTypes::Emoji { emoji } => {
-										if let Some(is_custom_emoji) = emoji.isCustomEmoji {
-											if is_custom_emoji {
-												message.push_str(format!(":{}:",&emoji.image.accessibility.accessibilityData.label).as_str());
-											} else {
-												message.push_str(&emoji.emojiId);
-											}
-										} else {
-											message.push_str(&emoji.emojiId);
-										}
-
-										//poll_name.push_str(&emoji.image.accessibility.accessibilityData.label);
-									},
-									RunsTypes::Unknown(unknown) => {
-										println!("UNKNOWN VALUE IN RUNS: {:#?}", unknown);
-									},
-								}
-							}
-							let id = liveChatTextMessageRenderer.id.clone();
-							let external_channel_id = liveChatTextMessageRenderer.authorExternalChannelId.clone();
-
-							// append to messages
-							messages.push(TextChat { message, id, external_channel_id });
-
-							num_messages += 1;
-							//println!("live_chat_text_message_renderer: {:#?}", liveChatTextMessageRenderer);
-						}, 
-						ChatItemType::LiveChatPlaceholderItemRenderer { liveChatPlaceholderItemRenderer } => {
-							// placeholder with no content
-							//println!("live_chat_placeholder_item_renderer: {:#?}", liveChatPlaceholderItemRenderer);
-						},
-						ChatItemType::LiveChatViewerEngagementMessageRenderer { liveChatViewerEngagementMessageRenderer } => {
-							// message about subscriber mode only and possibly other things
-							//println!("live_chat_viewer_engagement_message_renderer: {:#?}", liveChatViewerEngagementMessageRenderer);
+					num_gifts+=1;
+					// gift purchase
+					let sep = "=========gifting memberships start=========".black().on_truecolor(15, 157, 88);
+					println!("{}",sep);
+					
+					// print username and channel id
+					println!("username: {}, channel: https://youtube.com/channel/{}", gift_purchase.author.name, gift_purchase.author.channel_id);
+					// print number of gifted memberships
+					let num_gifted = match gift_purchase.number {
+						Some(number) => {
+							println!("gifted memberships: {}", number);
+							number
 						},
-						ChatItemType::LiveChatModeChangeMessageRenderer { liveChatModeChangeMessageRenderer } => {
-							// message about slow mode, members only mode, etc.
-							//println!("live_chat_mode_change_message_renderer: {:#?}", liveChatModeChangeMessageRenderer);
+						None => {
+							println!("error getting number of gifted memberships");
+							String::new()
 						},
+					};
 
-						ChatItemType::Unknown(s) => {
-							println!("UNKNOWN TYPE OF CHAT MESSAGE PLEASE REPORT: {:#?}", s);
-						}
-					}
 
-					//println!("add_chat_item_action: {:#?}", addChatItemAction.item);
-				},
-				Action::AddBannerToLiveChatCommand { addBannerToLiveChatCommand, .. } => {
-					//println!("add_banner_to_live_chat_command: {}", addBannerToLiveChatCommand);
-				},
-				Action::AddLiveChatTickerItemAction { addLiveChatTickerItemAction, .. } => {
-					// youtube join button and donations larger then 5$ are here
-					//println!("add_live_chat_ticker_item_action: {}", addLiveChatTickerItemAction);
-				},
-				Action::CloseLiveChatActionPanelAction { closeLiveChatActionPanelAction, .. } => {
-					//println!("close_live_chat_action_panel_action: {}", closeLiveChatActionPanelAction);
+					let (utc_timestamp, video_offset_ms) = event_times(&gift_purchase.time);
+					let timestring = time_formatter.format(gift_purchase.time.timestamp_text, gift_purchase.time.timestamp_usec, video_offset_ms);
+
+					// create gifting donation struct
+					let gift = export::Gift {
+						id: gift_purchase.id.clone(),
+						time: timestring.clone(),
+						video_id: stream_metadata.video_id.clone(),
+						title: stream_metadata.title.clone(),
+						channel: stream_metadata.channel.clone(),
+						release_timestamp: stream_metadata.release_timestamp,
+						timestamp: utc_timestamp,
+						video_offset_ms,
+						removed: false,
+						removed_reason: None,
+						thumbnail_url: gift_purchase.author.photo_url.expect("could not get thumbnail url"),
+						username: gift_purchase.author.name,
+						channel_id: gift_purchase.author.channel_id,
+						number: num_gifted,
+						header_color: i64::from_str_radix("0a8043", 16).expect("somhow failed to parse sponsor color"),
+						body_color: i64::from_str_radix("0f9d58", 16).expect("somhow failed to parse sponsor color"),
+					};
+
+					export!(ExportStructs::GiftingMembership(gift));
+
+
+					println!("==========gifting memberships end==========");
+
 				},
-				Action::LiveChatReportModerationStateCommand { liveChatReportModerationStateCommand, .. } => {
-					//println!("live_chat_report_moderation_state_command: {}", liveChatReportModerationStateCommand);
+				ChatEvent::GiftRedemption(gift_redemption) => {
+					num_redemptions+=1;
+					// message about person who got a gift
+
+					let sep = "=========membership redemption begins=========".black().on_truecolor(15, 157, 88);
+					println!("{}",sep);
+
+					let (utc_timestamp, video_offset_ms) = event_times(&gift_redemption.time);
+					let timestring = time_formatter.format(gift_redemption.time.timestamp_text, gift_redemption.time.timestamp_usec, video_offset_ms);
+					println!("time: {}", timestring);
+
+					let message = message_text!(gift_redemption.message);
+					let username = gift_redemption.author.name;
+					let channel_id = gift_redemption.author.channel_id;
+
+					// create gifting donation struct
+					let redemption = export::Redemption {
+						id: gift_redemption.id.clone(),
+						thumbnail_url: gift_redemption.author.photo_url.expect("could not get thumbnail url"),
+						username: username.clone(),
+						channel_id: channel_id.clone(),
+						sender: gift_redemption.sender.unwrap_or_else(|| "unknown".to_string()),
+						time: timestring.clone(),
+						video_id: stream_metadata.video_id.clone(),
+						title: stream_metadata.title.clone(),
+						channel: stream_metadata.channel.clone(),
+						release_timestamp: stream_metadata.release_timestamp,
+						timestamp: utc_timestamp,
+						video_offset_ms,
+						removed: false,
+						removed_reason: None,
+						header_color: i64::from_str_radix("0a8043", 16).expect("somhow failed to parse sponsor color"),
+						body_color: i64::from_str_radix("0f9d58", 16).expect("somhow failed to parse sponsor color"),
+					};
+
+					export!(ExportStructs::GiftMembership(redemption));
+
+					// print username and message
+					println!("{} {}", username, message);
+					// print recipient channel link
+					println!("channel: https://youtube.com/channel/{}", channel_id);
+					println!("==========membership redemption ends==========");
 				},
-				Action::RemoveBannerForLiveChatCommand { removeBannerForLiveChatCommand, .. } => {
-					//println!("remove_banner_for_live_chat_command: {}", removeBannerForLiveChatCommand);
+				ChatEvent::Sticker(sticker) => {
+
+					num_stickers+=1;
+					// backgroundColor is the raw decimal value of the color
+					// convert it to hex
+					let background_color = format!("{:x}", sticker.background_color);
+					// background_color is in argb format with 2 digits for each part
+					// split into alpha, red, green, blue
+					// this should be safe because the alpha is always ff
+					
+					assert!(background_color.len() == 8, "background color is not 8 characters long: {background_color}");
+
+					// split into alpha, red, green, blue u8 values
+					//let alpha = u8::from_str_radix(&background_color[0..2], 16).expect("could not parse alpha");
+					let red = u8::from_str_radix(&background_color[2..4], 16).expect("could not parse red");
+					let green = u8::from_str_radix(&background_color[4..6], 16).expect("could not parse green");
+					let blue = u8::from_str_radix(&background_color[6..8], 16).expect("could not parse blue");
+
+					let sep = "=========sticker start=========".black().on_truecolor(red, green, blue);
+					println!("{}",sep);
+
+					let event_date = rates::date_from_usec(sticker.time.timestamp_usec);
+					let (utc_timestamp, video_offset_ms) = event_times(&sticker.time);
+					let timestring = time_formatter.format(sticker.time.timestamp_text, sticker.time.timestamp_usec, video_offset_ms);
+
+					println!("time: {}", timestring);
+
+					// print username and channel link
+					println!("username: {}, channel: https://youtube.com/channel/{}", sticker.author.name, sticker.author.channel_id);
+					// print sticker cost
+					println!("sticker cost: {}", sticker.amount_text);
+					// print sticker description
+					println!("sticker description: {}", sticker.description);
+
+					let parsed_amount = sticker.amount;
+					let converted_value = match (&rate_table, &parsed_amount) {
+						(Some(rate_table), Some(amount)) => rate_table.convert(amount, event_date),
+						_ => None,
+					};
+					if rate_table.is_some() {
+						match &converted_value {
+							Some(value) => converted_stickers += value.minor_units,
+							None => num_unconverted += 1,
+						}
+					}
+
+					// create gifting donation struct
+					let donation = export::Sticker {
+						id: sticker.id.clone(),
+						time: timestring.clone(),
+						video_id: stream_metadata.video_id.clone(),
+						title: stream_metadata.title.clone(),
+						channel: stream_metadata.channel.clone(),
+						release_timestamp: stream_metadata.release_timestamp,
+						timestamp: utc_timestamp,
+						video_offset_ms,
+						removed: false,
+						removed_reason: None,
+						username: sticker.author.name,
+						channel_id: sticker.author.channel_id,
+						sticker_cost: sticker.amount_text,
+						currency: parsed_amount.as_ref().map(|amount| amount.currency.clone()),
+						amount_value: parsed_amount.as_ref().map(Amount::value),
+						converted_value: converted_value.as_ref().map(Amount::value),
+						base_currency: rate_table.as_ref().map(|rate_table| rate_table.base.clone()),
+						sticker_image_url: sticker.image_url.expect("could not get thumbnail url"),
+						sticker_description: sticker.description,
+						thumbnail_url: sticker.author.photo_url.expect("could not get thumbnail url"),
+						header_color: i64::from_str_radix(&background_color, 16).expect("somhow failed to parse sponsor color"),
+						body_color: i64::from_str_radix(&background_color, 16).expect("somhow failed to parse sponsor color"),
+					};
+
+					export!(ExportStructs::Sticker(donation));
+
+					println!("==========sticker end==========");
 				},
-				Action::RemoveChatItemAction { removeChatItemAction, .. } => {
-					let removed_message = removeChatItemAction.targetItemId;
-					println!("removed message id: {}", removed_message);
-					removed_messages.push(removed_message.clone());
+
+				ChatEvent::TextMessage(text_message) => {
+					// normal message
+
+					// store text and id
+					let message = message_text!(text_message.message);
+					let id = text_message.id;
+					let external_channel_id = text_message.author.channel_id;
+
+					let (utc_timestamp, video_offset_ms) = event_times(&text_message.time);
+					let timestring = time_formatter.format(text_message.time.timestamp_text, text_message.time.timestamp_usec, video_offset_ms);
+					let username = text_message.author.name;
+
+					// the sqlite archive keeps chat even when the output file doesn't
+					if shown && (args.output.export_chat || archive.is_some()) {
+						let chat_message = export::ChatMessage {
+							id: id.clone(),
+							username: username.clone(),
+							channel_id: external_channel_id.clone(),
+							time: timestring.clone(),
+							video_id: stream_metadata.video_id.clone(),
+							title: stream_metadata.title.clone(),
+							channel: stream_metadata.channel.clone(),
+							release_timestamp: stream_metadata.release_timestamp,
+							timestamp: utc_timestamp,
+							video_offset_ms,
+							removed: false,
+							removed_reason: None,
+							badges: text_message.author.badges,
+							message: message.clone(),
+						};
+						if args.output.export_chat {
+							export!(ExportStructs::TextMessage(chat_message));
+						} else if let Some(archive) = archive.as_mut() {
+							archive.insert_event(&id, &ExportStructs::TextMessage(chat_message)).expect("failed to write to sqlite database");
+						}
+					}
+
+					if keep_chat {
+						let chatter = *chatter_index.entry(external_channel_id.clone()).or_insert_with(|| {
+							chatters.push((external_channel_id, String::new()));
+							chatters.len() - 1
+						});
+						chatters[chatter].1 = username;
+						messages.push(TextChat { message, chatter, id, time: timestring });
+					}
+
+					if shown {
+						num_messages += 1;
+					}
+				}, 
+				ChatEvent::MessageDeleted(deleted) => {
+					let removed_message = deleted.target_id;
+					if shown {
+						println!("removed message id: {}", removed_message);
+					}
+					// what it removed is marked either way, the removal itself only counts when it passed the filters
+					if let Some(archive) = archive.as_mut() {
+						let result = if shown { archive.insert_removal("message", &removed_message) } else { archive.mark_removed("message", &removed_message) };
+						result.expect("failed to write to sqlite database");
+					}
+					removed_ids.entry(removed_message.clone()).or_insert("deleted");
+					if shown {
+						removed_messages.push((removed_message.clone(), deleted.video_offset_ms));
+					}
 				},
-				Action::RemoveChatItemByAuthorAction { removeChatItemByAuthorAction, .. } => {
+				ChatEvent::AuthorRemoved(author_removed) => {
 					// remove all messages by author
-					let channel_id = removeChatItemByAuthorAction.externalChannelId;
+					let channel_id = author_removed.channel_id;
 					let mut donations_holder = Vec::new();
 					let mut messages_holder = Vec::new();
 
-					// go over messages and donations and print them
-					for message in messages.iter() {
-						if message.external_channel_id == channel_id {
-							messages_holder.push(message.id.clone());
+					// only what the author sent so far is gone, a timed out author can come back
+					if let Some(chatter) = chatter_index.get(&channel_id) {
+						for message in messages.iter() {
+							if message.chatter == *chatter {
+								messages_holder.push(message.id.clone());
+							}
 						}
 					}
 					
 					for donation in donations.iter() {
-						// parse donation as ExportStructs
-						let donation_parsed: ExportStructs = serde_json::from_str(&donation).expect("could not parse donation");
-						match &donation_parsed {
-							ExportStructs::Donation(e) => {
-								if e.channel_id == channel_id {
-									donations_holder.push(donation_parsed);
-								}
-							},
-							ExportStructs::GiftingMembership(e) => {
-								if e.channel_id == channel_id {
-									donations_holder.push(donation_parsed);
-								}
-							},
-							ExportStructs::Sticker(e) => {
-								if e.channel_id == channel_id {
-									donations_holder.push(donation_parsed);
-								}
-							},
-							ExportStructs::Membership(e) => {
-								if e.channel_id == channel_id {
-									donations_holder.push(donation_parsed);
-								}
-							},
-							ExportStructs::GiftMembership(e) => {
-								if e.channel_id == channel_id {
-									donations_holder.push(donation_parsed);
-								}
-							},
+						if donation.channel_id() == channel_id {
+							removed_ids.entry(donation.id().to_string()).or_insert("author_wiped");
+							// text messages are already tracked in messages
+							if !matches!(donation, ExportStructs::TextMessage(_)) {
+								donations_holder.push(donation.clone());
+							}
 						}
 					}
+					for id in messages_holder.iter() {
+						removed_ids.entry(id.clone()).or_insert("author_wiped");
+					}
 
-
-					removed_channels.push((channel_id.clone(), donations_holder, messages_holder));
-					println!("removed message by channel: {channel_id}");
-				},
-				Action::ReplaceChatItemAction { replaceChatItemAction, .. } => {
-					//println!("replace_chat_item_action: {}", replaceChatItemAction);
-				},
-				Action::ShowLiveChatActionPanelAction { showLiveChatActionPanelAction, .. } => {
-					//println!("show_live_chat_action_panel_action: {}", showLiveChatActionPanelAction);
+					if let Some(archive) = archive.as_mut() {
+						let result = if shown { archive.insert_removal("author", &channel_id) } else { archive.mark_removed("author", &channel_id) };
+						result.expect("failed to write to sqlite database");
+					}
+					if shown {
+						removed_channels.push((channel_id.clone(), donations_holder, messages_holder, author_removed.video_offset_ms));
+						println!("removed message by channel: {channel_id}");
+					}
 				},
-				Action::UpdateLiveChatPollAction { updateLiveChatPollAction, .. } => {
+				ChatEvent::PollUpdate(poll) => {
 					// handle poll events
 
 					println!("==========poll update==========");
-					// print name of poll
-					let mut poll_name = String::new();
-					for run in updateLiveChatPollAction.pollToUpdate.pollRenderer.header.pollHeaderRenderer.pollQuestion.runs {
-						match run {
-							RunsTypes::Text { text, .. } => {
-								poll_name.push_str(&text);
-							},
-							RunsTypes::Emoji { emoji } => {
-								println!("emoji: {emoji:?}");
-
-								//poll_name.push_str(&emoji.image.accessibility.accessibilityData.label);
-							},
-							RunsTypes::Unknown(unknown) => {
-								eprintln!("unknown: {unknown:?}");
-							},
-
+					// runs we don't know yet go to stderr for polls
+					let poll_text = |text: event::Message| {
+						for unknown in text.unknown_runs.iter() {
+							eprintln!("unknown: {unknown:?}");
 						}
-					}
+						text.text
+					};
+					// print name of poll
+					let poll_name = poll_text(poll.question);
 					println!("poll question: {}", poll_name);
 
 					// print metadata
-					let mut metadata = String::new();
-					for run in updateLiveChatPollAction.pollToUpdate.pollRenderer.header.pollHeaderRenderer.metadataText.runs {
-						match run {
-							RunsTypes::Text { text, .. } => {
-								metadata.push_str(&text);
-							},
-							RunsTypes::Emoji { emoji } => {
-								println!("emoji: {emoji:?}");
-
-								//poll_name.push_str(&emoji.image.accessibility.accessibilityData.label);
-							},
-							RunsTypes::Unknown(unknown) => {
-								eprintln!("unknown: {unknown:?}");
-							},
-						}
-					}
+					let metadata = poll_text(poll.metadata);
 					println!("metadata: {}", metadata);
 
 					// print choices
-					for choice in updateLiveChatPollAction.pollToUpdate.pollRenderer.choices {
-						let mut choice_name = String::new();
-						for run in choice.text.runs {
-							match run {
-								RunsTypes::Text { text, .. } => {
-									choice_name.push_str(&text);
-								},
-								RunsTypes::Emoji { emoji } => {
-									println!("emoji: {emoji:?}");
-
-									//poll_name.push_str(&emoji.image.accessibility.accessibilityData.label);
-								},
-								RunsTypes::Unknown(unknown) => {
-									eprintln!("unknown: {unknown:?}");
-								},
-							}
-						}
+					let mut choices = Vec::new();
+					for choice in poll.choices {
+						let choice_name = poll_text(choice.text);
 						println!("choice: {}", choice_name);
-						println!("vote percentage: {}", choice.votePercentage.simpleText);
+						println!("vote percentage: {}", choice.vote_percentage);
+						choices.push((choice_name, choice.vote_percentage));
+					}
+					if let Some(archive) = archive.as_mut() {
+						archive.insert_poll(&poll.id, &poll_name, &metadata, &choices).expect("failed to write to sqlite database");
 					}
 					
 					
 					println!("==========poll update end==========");
 
 				},
-				Action::ReplaceLiveChatRendererAction { replaceLiveChatRendererAction, .. } => {
-					//println!("replace_live_chat_renderer_action: {}", replaceLiveChatRendererAction);
-				},
 
-				Action::Unknown(value) => {
-					println!("unknown action: {value:#?}");
+				// payloads from youtube that none of the library's structs match
+				ChatEvent::Unknown(unknown) => {
+					let key = drift.record(unknown.kind, unknown.value, line_number);
+					if unknown.kind == "action" {
+						println!("unknown action: {key} (line {line_number})");
+					} else {
+						println!("unknown type of chat message: {key} (line {line_number})");
+					}
 				}
 			}
 		}
 	}
 
-	
+	let invalid_lines = invalid_lines.finish().expect("failed to write to quarantine file");
+	// always report skipped lines, even with --dontprint
+	if !invalid_lines.is_empty() {
+		eprintln!("skipped {} invalid lines:", invalid_lines.len());
+		for (number, error) in invalid_lines.iter() {
+			eprintln!("line {number}: {error}");
+		}
+	}
+
+	print = args.print_stats;
 	println!("stats:");
 	// total messages
 	println!("total messages: {}", num_messages);
@@ -1221,9 +1043,71 @@ fn main() {
 	// average gift amount
 	println!("average gift amount: {}", f64::from(num_redemptions) / f64::from(num_gifts));
 
+	// totals converted to the base currency
+	if let Some(rate_table) = &rate_table {
+		let base_amount = |minor_units| Amount { currency: rate_table.base.clone(), minor_units };
+		println!("total superchat value: {}", base_amount(converted_superchats));
+		println!("total sticker value: {}", base_amount(converted_stickers));
+		println!("total value: {}", base_amount(converted_superchats + converted_stickers));
+		println!("amounts without a rate: {}", num_unconverted);
+	}
+
+	// unknown payloads, run with --drift-report to get the full json
+	if !drift.is_empty() && (args.print_events || args.print_stats) {
+		std::println!("unknown payloads (please report):");
+		for entry in drift.entries() {
+			std::println!("{} {}: seen {} times, first on line {}", entry.kind, entry.key, entry.count, entry.first_seen_line);
+		}
+	}
+	if let Some(path) = &args.input.drift_report {
+		drift.write_jsonl(path).expect("failed to write drift report");
+	}
+
+	if let Some(leaderboard) = leaderboard {
+		let donors = leaderboard.finish();
+
+		if let Some(top) = args.top {
+			let print_donor = |place: usize, donor: &leaderboard::Donor| {
+				let spend = match donor.converted() {
+					Some(total) => format!("{total} ({})", donor.totals_string()),
+					None => donor.totals_string(),
+				};
+				println!(
+					"{}. {} (https://youtube.com/channel/{}): spent: [{}], superchats: {}, stickers: {}, memberships: {}, gifted memberships: {}",
+					place + 1, donor.username, donor.channel_id, spend, donor.superchats, donor.stickers, donor.memberships, donor.gifted_memberships,
+				);
+			};
+			if let Some(rate_table) = &rate_table {
+				println!("top donors by total in {}:", rate_table.base);
+				for (place, donor) in donors.iter().take(top).enumerate() {
+					print_donor(place, donor);
+				}
+			} else {
+				// different currencies can't be compared without --rates
+				for currency in leaderboard::currencies(&donors) {
+					println!("top donors in {currency} (use --rates to rank across currencies):");
+					for (place, donor) in leaderboard::ranked_in(&donors, currency).into_iter().take(top).enumerate() {
+						print_donor(place, donor);
+					}
+				}
+				let mut gifters: Vec<&leaderboard::Donor> = donors.iter().filter(|donor| donor.gifted_memberships > 0).collect();
+				if !gifters.is_empty() {
+					gifters.sort_by(|a, b| b.gifted_memberships.cmp(&a.gifted_memberships).then(a.channel_id.cmp(&b.channel_id)));
+					println!("top gifters:");
+					for (place, donor) in gifters.into_iter().take(top).enumerate() {
+						print_donor(place, donor);
+					}
+				}
+			}
+		}
+		if let Some(path) = &args.leaderboard {
+			leaderboard::write(&donors, path).expect("failed to write leaderboard");
+		}
+	}
+
 	// print removed channels and messages
 	println!("removed channels:----------------------------");
-	for (channel,removed_donations,removed_messages) in removed_channels.iter() {
+	for (channel,removed_donations,removed_messages,_) in removed_channels.iter() {
 		println!("removed channel: -------------{}-------------", channel);
 		for donation in removed_donations.iter() {
 			match donation {
@@ -1242,6 +1126,9 @@ fn main() {
 				ExportStructs::GiftMembership(e) => {
 					println!("removed gift membership redemption: {:?}", e);
 				},
+				ExportStructs::TextMessage(e) => {
+					println!("removed text message: {:?}", e);
+				},
 			}
 		}
 		for message_id in removed_messages.iter() {
@@ -1254,7 +1141,10 @@ fn main() {
 		}
 	}
 	println!("removed messages:--------------------------------");
-	for message_id in removed_messages.iter() {
+	if !keep_chat && (!removed_channels.is_empty() || !removed_messages.is_empty()) {
+		println!("(chat isn't kept while extracting, run stats or use --modlog to see the removed messages)");
+	}
+	for (message_id, _) in removed_messages.iter() {
 		// lookup message in messages and print it
 		for message in messages.iter() {
 			if message.id == *message_id {
@@ -1263,35 +1153,200 @@ fn main() {
 		}
 	}
 
-	let mut output = String::new();
-	for donation in donations {
-		output.push_str(&format!("{donation}\n"));
+	if let Some(archive) = archive {
+		archive.finish().expect("failed to write to sqlite database");
 	}
 
-	// if args.outputfile then write all donations to file
-	if let Some(outputfile) = args.outputfile {
-		let mut file = std::fs::File::create(outputfile).expect("failed to create file");
-		file.write_all(output.as_bytes()).expect("failed to write to file");
+	// mark removed events now that every removal is known
+	if !removed_ids.is_empty() {
+		for donation in donations.iter_mut() {
+			if let Some(reason) = removed_ids.get(donation.id()) {
+				donation.mark_removed(reason);
+			}
+		}
 	}
-	
-	if args.live {
-		use interprocess::local_socket::LocalSocketStream;
-		// Start the client
-		let mut client = match LocalSocketStream::connect("@live_donations") {
-			Ok(client) => client,
-			Err(e) => {
-				eprintln!("failed to connect to server: {}", e);
-				return;
+
+	if let Some(path) = &args.modlog {
+		let mut log = modlog::ModLog::default();
+		for (channel_id, removed_donations, removed_message_ids, offset) in removed_channels.iter() {
+			let removed_chat: Vec<&TextChat> = messages.iter().filter(|message| removed_message_ids.contains(&message.id)).collect();
+			// the events from the output, which have removed set by now
+			let paid_events: Vec<&ExportStructs> = donations.iter()
+				.filter(|event| removed_donations.iter().any(|removed| removed.id() == event.id()))
+				.collect();
+			let username = removed_chat.last().map(|message| chatters[message.chatter].1.as_str())
+				.or_else(|| paid_events.last().map(|event| modlog::describe(event).0));
+			log.wipes.push(modlog::Wipe {
+				channel_id,
+				username,
+				time: offset.map(|offset| time_formatter.format_video_offset(offset, stream_metadata.release_timestamp)),
+				video_offset_ms: *offset,
+				messages: removed_chat.iter().map(|message| modlog::Message { id: &message.id, time: &message.time, text: &message.message }).collect(),
+				paid_events,
+			});
+		}
+		for (message_id, offset) in removed_messages.iter() {
+			let message = messages.iter().find(|message| message.id == *message_id);
+			let paid_event = donations.iter().find(|event| event.id() == message_id);
+			let mut deletion = modlog::Deletion {
+				id: message_id,
+				channel_id: None,
+				username: None,
+				message_time: None,
+				text: None,
+				time: offset.map(|offset| time_formatter.format_video_offset(offset, stream_metadata.release_timestamp)),
+				video_offset_ms: *offset,
+				paid_event,
+			};
+			if let Some(message) = message {
+				deletion.channel_id = Some(&chatters[message.chatter].0);
+				deletion.username = Some(&chatters[message.chatter].1);
+				deletion.message_time = Some(&message.time);
+				deletion.text = Some(&message.message);
+			} else if let Some(event) = paid_event {
+				let (username, channel_id, time, text) = modlog::describe(event);
+				deletion.channel_id = Some(channel_id);
+				deletion.username = Some(username);
+				deletion.message_time = Some(time);
+				deletion.text = text;
 			}
-		};
-		// push the filename to the beginning of output
-		// remove file extension from filename
-		let mut filename = args.file;
-		// extension is .live_chat.json.part
-		filename.truncate(filename.len() - 20);
-		output.insert_str(0, &format!("{}\n", filename));
-
-		// Send a message from the client to the server
-		client.write_all(output.as_bytes()).unwrap();
+			log.deletions.push(deletion);
+		}
+		modlog::write(&log, path).expect("failed to write moderation log");
+	}
+
+	if let Some(path) = &args.html {
+		let mut stats = vec![
+			("superchats".to_string(), num_superchats.to_string()),
+			("stickers".to_string(), num_stickers.to_string()),
+			("memberships".to_string(), num_memberships.to_string()),
+			("gifted membership purchases".to_string(), num_gifts.to_string()),
+			("gifted memberships redeemed".to_string(), num_redemptions.to_string()),
+			("messages".to_string(), num_messages.to_string()),
+			("message wipes".to_string(), removed_channels.len().to_string()),
+			("deleted messages".to_string(), removed_messages.len().to_string()),
+		];
+		if let Some(rate_table) = &rate_table {
+			let base_amount = |minor_units| Amount { currency: rate_table.base.clone(), minor_units };
+			stats.push(("superchat value".to_string(), base_amount(converted_superchats).to_string()));
+			stats.push(("sticker value".to_string(), base_amount(converted_stickers).to_string()));
+			stats.push(("total value".to_string(), base_amount(converted_superchats + converted_stickers).to_string()));
+		}
+		let title = stream_metadata.title.as_ref().unwrap_or(&stream_name);
+		let report = html::Report { title, stats, emoji_images: &emoji_images };
+		std::fs::write(path, report.render(donations.iter())).expect("failed to write html report");
+	}
+
+	// if args.output.outputfile then write all donations to file
+	if let Some(outputfile) = args.output.outputfile {
+		match args.output.format {
+			OutputFormat::Jsonl => {
+				let file = std::fs::File::create(outputfile).expect("failed to create file");
+				let mut file = std::io::BufWriter::new(file);
+				for donation in donations.iter() {
+					let json = serde_json::to_string(donation).expect("could not serialize donation");
+					writeln!(file, "{json}").expect("failed to write to file");
+				}
+				file.flush().expect("failed to write to file");
+			},
+			OutputFormat::Csv => csv_output::write(&donations, &outputfile, b',').expect("failed to write to file"),
+			OutputFormat::Tsv => csv_output::write(&donations, &outputfile, b'\t').expect("failed to write to file"),
+		}
+	}
+	
+	if args.queue {
+		let state_path = args.queue_state.clone().unwrap_or_else(|| format!("{}.queue.json", file_path));
+		queue::run(donations, &state_path, &args.input.base_currency, rate_table.as_ref()).expect("reading queue failed");
+	}
+
+	if let Some(live) = live {
+		live.finish();
+	}
+}
+
+// the report subcommand, the summary is worked out again from what the export kept
+fn write_report(args: ReportArgs) {
+	let file = std::fs::File::open(&args.file).expect("could not open export file");
+	let events = export::read(std::io::BufReader::new(file)).expect("could not read export file");
+
+	let title = args.title.clone()
+		.or_else(|| events.iter().find_map(|event| event.title().or(event.video_id()).map(String::from)))
+		.unwrap_or_else(|| metadata::stream_name(&args.file));
+
+	let mut num_superchats = 0;
+	let mut num_stickers = 0;
+	let mut num_memberships = 0;
+	let mut num_gifts = 0;
+	let mut num_redemptions = 0;
+	let mut num_messages = 0;
+	let mut num_removed = 0;
+	// totals in the base currency, only there when extract ran with --rates
+	let mut converted_superchats = 0;
+	let mut converted_stickers = 0;
+	let mut base_currency = None;
+	for event in events.iter() {
+		match event {
+			ExportStructs::Donation(e) => {
+				num_superchats += 1;
+				base_currency = base_currency.or(e.base_currency.as_deref());
+				if let (Some(base), Some(value)) = (base_currency, e.converted_value) {
+					converted_superchats += Amount::from_value(base, value).minor_units;
+				}
+				num_removed += usize::from(e.removed);
+			},
+			ExportStructs::Sticker(e) => {
+				num_stickers += 1;
+				base_currency = base_currency.or(e.base_currency.as_deref());
+				if let (Some(base), Some(value)) = (base_currency, e.converted_value) {
+					converted_stickers += Amount::from_value(base, value).minor_units;
+				}
+				num_removed += usize::from(e.removed);
+			},
+			ExportStructs::Membership(e) => {
+				num_memberships += 1;
+				num_removed += usize::from(e.removed);
+			},
+			ExportStructs::GiftingMembership(e) => {
+				num_gifts += 1;
+				num_removed += usize::from(e.removed);
+			},
+			ExportStructs::GiftMembership(e) => {
+				num_redemptions += 1;
+				num_removed += usize::from(e.removed);
+			},
+			ExportStructs::TextMessage(e) => {
+				num_messages += 1;
+				num_removed += usize::from(e.removed);
+			},
+		}
+	}
+
+	let mut stats = vec![
+		("superchats".to_string(), num_superchats.to_string()),
+		("stickers".to_string(), num_stickers.to_string()),
+		("memberships".to_string(), num_memberships.to_string()),
+		("gifted membership purchases".to_string(), num_gifts.to_string()),
+		("gifted memberships redeemed".to_string(), num_redemptions.to_string()),
+	];
+	// chat is only in the export with --export-chat
+	if num_messages > 0 {
+		stats.push(("messages".to_string(), num_messages.to_string()));
+	}
+	stats.push(("removed by moderators".to_string(), num_removed.to_string()));
+	if let Some(base) = base_currency {
+		let base_amount = |minor_units| Amount { currency: base.to_string(), minor_units };
+		stats.push(("superchat value".to_string(), base_amount(converted_superchats).to_string()));
+		stats.push(("sticker value".to_string(), base_amount(converted_stickers).to_string()));
+		stats.push(("total value".to_string(), base_amount(converted_superchats + converted_stickers).to_string()));
+	}
+
+	let report = match args.format {
+		// the export only has the :shortcut: of custom emojis, not their images
+		ReportFormat::Html => html::Report { title: &title, stats, emoji_images: &std::collections::BTreeMap::new() }.render(events.iter()),
+		ReportFormat::Markdown => markdown::Report { title: &title, stats }.render(events.iter()),
+	};
+	match &args.output {
+		Some(path) => std::fs::write(path, report).expect("failed to write report"),
+		None => print!("{report}"),
 	}
 }
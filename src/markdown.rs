@@ -0,0 +1,141 @@
+// markdown report of every paid event, one table row each, for pasting into discord or a github issue
+// custom emojis stay as :shortcut: text
+
+use superchat_extractor::currency::Amount;
+use superchat_extractor::export::ExportStructs;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+pub struct Report<'a> {
+	pub title: &'a str,
+	// the summary table at the top
+	pub stats: Vec<(String, String)>,
+}
+
+impl Report<'_> {
+	pub fn render<'a>(&self, events: impl Iterator<Item = &'a ExportStructs>) -> String {
+		let mut markdown = String::new();
+		let _ = writeln!(markdown, "# {}\n", escape(self.title));
+		for (label, value) in self.stats.iter() {
+			let _ = writeln!(markdown, "- **{}:** {}", escape(label), escape(value));
+		}
+		// amounts in different currencies can't be added up, so one line for each
+		let mut totals: BTreeMap<String, i64> = BTreeMap::new();
+		let mut rows = String::new();
+		for event in events {
+			let (currency, value) = match event {
+				ExportStructs::Donation(e) => (&e.currency, e.amount_value),
+				ExportStructs::Sticker(e) => (&e.currency, e.amount_value),
+				_ => (&None, None),
+			};
+			if let (Some(currency), Some(value)) = (currency, value) {
+				*totals.entry(currency.clone()).or_insert(0) += Amount::from_value(currency, value).minor_units;
+			}
+			self.render_row(&mut rows, event);
+		}
+		for (currency, minor_units) in totals {
+			let total = Amount { currency, minor_units };
+			let _ = writeln!(markdown, "- **spent in {}:** {}", escape(&total.currency), escape(&total.to_string()));
+		}
+
+		markdown.push_str("\n| time | name | event | message |\n| --- | --- | --- | --- |\n");
+		markdown.push_str(&rows);
+		markdown
+	}
+
+	fn render_row(&self, markdown: &mut String, event: &ExportStructs) {
+		let (time, video_id, video_offset_ms, username, channel_id, headline, message) = match event {
+			ExportStructs::Donation(e) => (&e.time, &e.video_id, e.video_offset_ms, &e.username, &e.channel_id, e.amount.clone(), e.message.clone()),
+			ExportStructs::Sticker(e) => (&e.time, &e.video_id, e.video_offset_ms, &e.username, &e.channel_id, format!("{} sticker", e.sticker_cost), Some(e.sticker_description.clone())),
+			ExportStructs::Membership(e) => (&e.time, &e.video_id, e.video_offset_ms, &e.username, &e.channel_id, e.months.clone(), e.message.clone()),
+			ExportStructs::GiftingMembership(e) => (&e.time, &e.video_id, e.video_offset_ms, &e.username, &e.channel_id, format!("gifted {} memberships", e.number), None),
+			ExportStructs::GiftMembership(e) => (&e.time, &e.video_id, e.video_offset_ms, &e.username, &e.channel_id, format!("received a gift membership from {}", e.sender), None),
+			// normal chat doesn't get a row
+			ExportStructs::TextMessage(_) => return,
+		};
+
+		// a link into the vod when the offset is known
+		let time = match (video_id, video_offset_ms) {
+			(Some(video_id), Some(offset)) => format!("[{}](https://youtu.be/{}?t={})", escape(time), url_part(video_id), (offset / 1000).max(0)),
+			_ => escape(time),
+		};
+		let _ = writeln!(
+			markdown,
+			"| {} | [{}](https://youtube.com/channel/{}) | {} | {} |",
+			time, escape(username), url_part(channel_id), escape(&headline), message.as_deref().map(escape).unwrap_or_default(),
+		);
+	}
+}
+
+// keeps names and messages from breaking the table or turning into formatting
+fn escape(text: &str) -> String {
+	let mut escaped = String::with_capacity(text.len());
+	for c in text.chars() {
+		match c {
+			'\n' | '\r' => escaped.push(' '),
+			'|' | '*' | '_' | '[' | ']' | '`' | '<' | '>' | '#' | '\\' => {
+				escaped.push('\\');
+				escaped.push(c);
+			},
+			_ => escaped.push(c),
+		}
+	}
+	escaped
+}
+
+// ids come from the chat file, anything youtube wouldn't put in one is percent encoded so it can't end the link
+fn url_part(text: &str) -> String {
+	let mut encoded = String::with_capacity(text.len());
+	for byte in text.bytes() {
+		if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+			encoded.push(char::from(byte));
+		} else {
+			let _ = write!(encoded, "%{byte:02X}");
+		}
+	}
+	encoded
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use superchat_extractor::export;
+
+	fn donation(channel_id: &str, username: &str, amount: &str, message: &str) -> ExportStructs {
+		let event = serde_json::json!({
+			"type": "Donation", "id": "d1", "time": "1:05", "username": username, "channel_id": channel_id, "amount": amount,
+			"message": message, "header_color": 0, "body_color": 0, "thumbnail_url": "",
+			"video_id": "dQw4w9WgXcQ", "video_offset_ms": 65_400,
+		});
+		export::parse_line(&event.to_string()).unwrap()
+	}
+
+	fn render(events: &[ExportStructs]) -> String {
+		Report { title: "Stream #1", stats: vec![("superchats".to_string(), events.len().to_string())] }.render(events.iter())
+	}
+
+	#[test]
+	fn renders_an_empty_stream() {
+		assert_eq!(render(&[]), "# Stream \\#1\n\n- **superchats:** 0\n\n| time | name | event | message |\n| --- | --- | --- | --- |\n");
+	}
+
+	#[test]
+	fn escapes_names_messages_and_link_targets() {
+		let markdown = render(&[donation("UCa) [x](https://evil", "*Ann* | [me]", "$5.00", "line\nbreak `code` <b>")]);
+		let row = markdown.lines().last().unwrap();
+		assert_eq!(
+			row,
+			"| [1:05](https://youtu.be/dQw4w9WgXcQ?t=65) | [\\*Ann\\* \\| \\[me\\]](https://youtube.com/channel/UCa%29%20%5Bx%5D%28https%3A%2F%2Fevil) | $5.00 | line break \\`code\\` \\<b\\> |",
+		);
+	}
+
+	#[test]
+	fn totals_per_currency() {
+		let markdown = render(&[
+			donation("UCa", "Ann", "$5.00", "hi"),
+			donation("UCb", "Bob", "¥1,000", "hi"),
+			donation("UCc", "Cat", "$2.50", "hi"),
+		]);
+		assert!(markdown.contains("- **spent in JPY:** 1000 JPY\n- **spent in USD:** 7.50 USD\n"));
+	}
+}
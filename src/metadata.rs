@@ -0,0 +1,149 @@
+// what we know about the stream a chat file belongs to
+// yt-dlp names files "Title [id].live_chat.json" by default and can write "Title [id].info.json" next to them
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct StreamMetadata {
+	#[serde(default)]
+	pub video_id: Option<String>,
+	#[serde(default)]
+	pub title: Option<String>,
+	#[serde(default)]
+	pub channel: Option<String>,
+	#[serde(default)]
+	pub channel_id: Option<String>,
+	// unix seconds the stream started at
+	#[serde(default)]
+	pub release_timestamp: Option<i64>,
+}
+
+// the parts of the .info.json we care about, it has a lot more
+#[derive(Deserialize)]
+struct InfoJson {
+	id: Option<String>,
+	title: Option<String>,
+	// uploader is what older yt-dlp versions call the channel
+	channel: Option<String>,
+	uploader: Option<String>,
+	channel_id: Option<String>,
+	release_timestamp: Option<i64>,
+}
+
+impl StreamMetadata {
+	// never fails, anything that can't be found is left empty
+	pub fn load(file: &str) -> StreamMetadata {
+		let name = stream_name(file);
+		let (title, video_id) = split_name(&name);
+		let mut metadata = StreamMetadata { video_id, title, ..Default::default() };
+
+		let info_path = Path::new(file).with_file_name(format!("{name}.info.json"));
+		if let Ok(contents) = std::fs::read_to_string(&info_path) {
+			match serde_json::from_str::<InfoJson>(&contents) {
+				Ok(info) => {
+					metadata.video_id = info.id.or(metadata.video_id);
+					metadata.title = info.title.or(metadata.title);
+					metadata.channel = info.channel.or(info.uploader);
+					metadata.channel_id = info.channel_id;
+					metadata.release_timestamp = info.release_timestamp;
+				},
+				Err(e) => eprintln!("ignoring {}: {e}", info_path.display()),
+			}
+		}
+		metadata
+	}
+}
+
+// "Title [id].live_chat.json.part" -> "Title [id]"
+pub fn stream_name(file: &str) -> String {
+	let name = Path::new(file)
+		.file_name()
+		.map(|name| name.to_string_lossy().to_string())
+		.unwrap_or_else(|| file.to_string());
+	let name = name.strip_suffix(".part").unwrap_or(&name);
+	let name = name.strip_suffix(".json").unwrap_or(name);
+	let name = name.strip_suffix(".live_chat").unwrap_or(name);
+	name.to_string()
+}
+
+// "Title [id]" -> title and id, a bare "id" has no title
+fn split_name(name: &str) -> (Option<String>, Option<String>) {
+	if let Some(rest) = name.strip_suffix(']') {
+		if let Some((title, id)) = rest.rsplit_once(" [") {
+			if is_video_id(id) {
+				return (Some(title.to_string()), Some(id.to_string()));
+			}
+		}
+	}
+	if is_video_id(name) {
+		return (None, Some(name.to_string()));
+	}
+	(Some(name.to_string()), None)
+}
+
+// youtube ids are 11 characters of url safe base64
+fn is_video_id(id: &str) -> bool {
+	id.len() == 11 && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn temp_dir(name: &str) -> std::path::PathBuf {
+		let dir = std::env::temp_dir().join(format!("superchat-extractor-metadata-{}-{name}", std::process::id()));
+		let _ = std::fs::remove_dir_all(&dir);
+		std::fs::create_dir_all(&dir).unwrap();
+		dir
+	}
+
+	#[test]
+	fn names_from_chat_files() {
+		let cases = [
+			("dir/Title [dQw4w9WgXcQ].live_chat.json", "Title [dQw4w9WgXcQ]", Some("Title"), Some("dQw4w9WgXcQ")),
+			("Title [dQw4w9WgXcQ].live_chat.json.part", "Title [dQw4w9WgXcQ]", Some("Title"), Some("dQw4w9WgXcQ")),
+			// brackets in the title itself, only the last ones are the id
+			("[live] A [b] [dQw4w9WgXcQ].live_chat.json", "[live] A [b] [dQw4w9WgXcQ]", Some("[live] A [b]"), Some("dQw4w9WgXcQ")),
+			("dQw4w9WgXcQ.live_chat.json", "dQw4w9WgXcQ", None, Some("dQw4w9WgXcQ")),
+			("chat.json", "chat", Some("chat"), None),
+			// not 11 characters, so part of the title
+			("Title [short].live_chat.json", "Title [short]", Some("Title [short]"), None),
+		];
+		for (file, name, title, video_id) in cases {
+			assert_eq!(stream_name(file), name, "{file}");
+			assert_eq!(split_name(name), (title.map(String::from), video_id.map(String::from)), "{file}");
+		}
+	}
+
+	#[test]
+	fn reads_the_info_json_next_to_the_chat() {
+		let dir = temp_dir("info");
+		let chat = dir.join("Title [dQw4w9WgXcQ].live_chat.json.part");
+		std::fs::write(
+			dir.join("Title [dQw4w9WgXcQ].info.json"),
+			r#"{"id":"dQw4w9WgXcQ","title":"Full: Title","uploader":"Someone","channel_id":"UCa","release_timestamp":1700000000,"formats":[]}"#,
+		).unwrap();
+		let metadata = StreamMetadata::load(chat.to_str().unwrap());
+		assert_eq!(metadata.title.as_deref(), Some("Full: Title"));
+		assert_eq!(metadata.channel.as_deref(), Some("Someone"));
+		assert_eq!(metadata.channel_id.as_deref(), Some("UCa"));
+		assert_eq!(metadata.release_timestamp, Some(1700000000));
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn falls_back_to_the_file_name_without_a_usable_info_json() {
+		let dir = temp_dir("missing");
+		let chat = dir.join("Title [dQw4w9WgXcQ].live_chat.json");
+		let missing = StreamMetadata::load(chat.to_str().unwrap());
+		std::fs::write(dir.join("Title [dQw4w9WgXcQ].info.json"), "{not json").unwrap();
+		let invalid = StreamMetadata::load(chat.to_str().unwrap());
+		for metadata in [missing, invalid] {
+			assert_eq!(metadata.title.as_deref(), Some("Title"));
+			assert_eq!(metadata.video_id.as_deref(), Some("dQw4w9WgXcQ"));
+			assert_eq!((metadata.channel, metadata.release_timestamp), (None, None));
+		}
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+}
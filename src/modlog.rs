@@ -0,0 +1,219 @@
+// moderation report of every author wipe and single deleted message for reviewing bans after a stream
+// json keeps wipes and deletions separate, csv has one row per removed item
+
+use superchat_extractor::export::ExportStructs;
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Default)]
+pub struct ModLog<'a> {
+	pub wipes: Vec<Wipe<'a>>,
+	pub deletions: Vec<Deletion<'a>>,
+}
+
+// RemoveChatItemByAuthorAction, a ban or a timeout
+#[derive(Serialize, Debug)]
+pub struct Wipe<'a> {
+	pub channel_id: &'a str,
+	// the name from their most recent message or event, if they sent any
+	pub username: Option<&'a str>,
+	// in --time-format, worked out from the offset into the video since that is all a wipe comes with
+	pub time: Option<String>,
+	pub video_offset_ms: Option<i64>,
+	pub messages: Vec<Message<'a>>,
+	pub paid_events: Vec<&'a ExportStructs>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Message<'a> {
+	pub id: &'a str,
+	pub time: &'a str,
+	pub text: &'a str,
+}
+
+// RemoveChatItemAction, one message taken down
+#[derive(Serialize, Debug)]
+pub struct Deletion<'a> {
+	pub id: &'a str,
+	// author and text are only known when the message was seen before it was removed
+	pub channel_id: Option<&'a str>,
+	pub username: Option<&'a str>,
+	pub message_time: Option<&'a str>,
+	pub text: Option<&'a str>,
+	pub time: Option<String>,
+	pub video_offset_ms: Option<i64>,
+	// when a superchat or another paid event was the thing removed
+	pub paid_event: Option<&'a ExportStructs>,
+}
+
+// username, channel id, time and message of a paid event
+pub fn describe(event: &ExportStructs) -> (&str, &str, &str, Option<&str>) {
+	let text = match event {
+		ExportStructs::Donation(e) => e.message.as_deref(),
+		ExportStructs::Membership(e) => e.message.as_deref(),
+		ExportStructs::Sticker(e) => Some(e.sticker_description.as_str()),
+		ExportStructs::TextMessage(e) => Some(e.message.as_str()),
+		ExportStructs::GiftMembership(_) | ExportStructs::GiftingMembership(_) => None,
+	};
+	(event.username(), event.channel_id(), event.time(), text)
+}
+
+fn event_type(event: &ExportStructs) -> &'static str {
+	match event {
+		ExportStructs::Donation(_) => "Donation",
+		ExportStructs::Membership(_) => "Membership",
+		ExportStructs::GiftMembership(_) => "GiftMembership",
+		ExportStructs::GiftingMembership(_) => "GiftingMembership",
+		ExportStructs::Sticker(_) => "Sticker",
+		ExportStructs::TextMessage(_) => "TextMessage",
+	}
+}
+
+fn event_amount(event: &ExportStructs) -> Option<&str> {
+	match event {
+		ExportStructs::Donation(e) => Some(&e.amount),
+		ExportStructs::Sticker(e) => Some(&e.sticker_cost),
+		ExportStructs::Membership(e) => Some(&e.months),
+		ExportStructs::GiftingMembership(e) => Some(&e.number),
+		ExportStructs::GiftMembership(_) | ExportStructs::TextMessage(_) => None,
+	}
+}
+
+// the Row fields, written up front so a stream without removals still gets a header
+const HEADER: &[&str] = &[
+	"action", "channel_id", "username", "removed_time", "removed_video_offset_ms",
+	"item_type", "item_id", "item_time", "amount", "text",
+];
+
+#[derive(Serialize, Default)]
+struct Row<'a> {
+	// "wipe" or "deletion"
+	action: &'static str,
+	channel_id: Option<&'a str>,
+	username: Option<&'a str>,
+	removed_time: Option<&'a str>,
+	removed_video_offset_ms: Option<i64>,
+	// "TextMessage" for chat, otherwise the export type of the paid event
+	item_type: Option<&'static str>,
+	item_id: Option<&'a str>,
+	item_time: Option<&'a str>,
+	amount: Option<&'a str>,
+	text: Option<&'a str>,
+}
+
+pub fn write(log: &ModLog, path: &str) -> std::io::Result<()> {
+	let file = std::fs::File::create(path)?;
+	if path.to_lowercase().ends_with(".json") {
+		serde_json::to_writer_pretty(file, log)?;
+		return Ok(());
+	}
+	write_csv(log, file)
+}
+
+fn write_csv(log: &ModLog, output: impl std::io::Write) -> std::io::Result<()> {
+	let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(output);
+	writer.write_record(HEADER)?;
+	for wipe in log.wipes.iter() {
+		let row = || Row {
+			action: "wipe",
+			channel_id: Some(wipe.channel_id),
+			username: wipe.username,
+			removed_time: wipe.time.as_deref(),
+			removed_video_offset_ms: wipe.video_offset_ms,
+			..Default::default()
+		};
+		// a wipe of someone who said nothing we saw still gets a row
+		if wipe.messages.is_empty() && wipe.paid_events.is_empty() {
+			writer.serialize(row())?;
+		}
+		for message in wipe.messages.iter() {
+			writer.serialize(Row { item_type: Some("TextMessage"), item_id: Some(message.id), item_time: Some(message.time), text: Some(message.text), ..row() })?;
+		}
+		for event in wipe.paid_events.iter() {
+			let (_, _, time, text) = describe(event);
+			writer.serialize(Row { item_type: Some(event_type(event)), item_id: Some(event.id()), item_time: Some(time), amount: event_amount(event), text, ..row() })?;
+		}
+	}
+	for deletion in log.deletions.iter() {
+		writer.serialize(Row {
+			action: "deletion",
+			channel_id: deletion.channel_id,
+			username: deletion.username,
+			removed_time: deletion.time.as_deref(),
+			removed_video_offset_ms: deletion.video_offset_ms,
+			item_type: Some(deletion.paid_event.map(event_type).unwrap_or("TextMessage")),
+			item_id: Some(deletion.id),
+			item_time: deletion.message_time,
+			amount: deletion.paid_event.and_then(event_amount),
+			text: deletion.text,
+		})?;
+	}
+	writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use superchat_extractor::export;
+
+	fn write_string(log: &ModLog) -> String {
+		let mut output = Vec::new();
+		write_csv(log, &mut output).unwrap();
+		String::from_utf8(output).unwrap()
+	}
+
+	#[test]
+	fn writes_the_header_without_removals() {
+		assert_eq!(write_string(&ModLog::default()), format!("{}\n", HEADER.join(",")));
+	}
+
+	#[test]
+	fn header_matches_the_row_fields() {
+		let mut writer = csv::Writer::from_writer(Vec::new());
+		writer.serialize(Row::default()).unwrap();
+		let written = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+		assert_eq!(written.lines().next().unwrap(), HEADER.join(","));
+	}
+
+	#[test]
+	fn one_row_per_message_and_paid_event_of_a_wipe() {
+		let donation = export::parse_line(r#"{"type":"Donation","id":"d1","time":"0:10","username":"Bob","channel_id":"UCbbb","amount":"$5.00","message":"hi","header_color":0,"body_color":0,"thumbnail_url":""}"#).unwrap();
+		let gift = export::parse_line(r#"{"type":"GiftingMembership","id":"g1","time":"0:20","username":"Bob","channel_id":"UCbbb","number":"5","header_color":0,"body_color":0,"thumbnail_url":""}"#).unwrap();
+		let log = ModLog {
+			wipes: vec![Wipe {
+				channel_id: "UCbbb",
+				username: Some("Bob"),
+				time: Some("0:30".to_string()),
+				video_offset_ms: Some(30_000),
+				messages: vec![Message { id: "m1", time: "0:05", text: "spam, spam" }],
+				paid_events: vec![&donation, &gift],
+			}],
+			deletions: Vec::new(),
+		};
+		assert_eq!(write_string(&log).lines().skip(1).collect::<Vec<_>>(), [
+			r#"wipe,UCbbb,Bob,0:30,30000,TextMessage,m1,0:05,,"spam, spam""#,
+			"wipe,UCbbb,Bob,0:30,30000,Donation,d1,0:10,$5.00,hi",
+			"wipe,UCbbb,Bob,0:30,30000,GiftingMembership,g1,0:20,5,",
+		]);
+	}
+
+	#[test]
+	fn wipes_of_silent_authors_and_single_deletions() {
+		let log = ModLog {
+			wipes: vec![Wipe { channel_id: "UCccc", username: None, time: None, video_offset_ms: None, messages: Vec::new(), paid_events: Vec::new() }],
+			deletions: vec![Deletion {
+				id: "m2",
+				channel_id: Some("UCaaa"),
+				username: Some("Ann"),
+				message_time: Some("0:40"),
+				text: Some("bad words"),
+				time: Some("0:41".to_string()),
+				video_offset_ms: Some(41_000),
+				paid_event: None,
+			}],
+		};
+		assert_eq!(write_string(&log).lines().skip(1).collect::<Vec<_>>(), [
+			"wipe,UCccc,,,,,,,,",
+			"deletion,UCaaa,Ann,0:41,41000,TextMessage,m2,0:40,,bad words",
+		]);
+	}
+}
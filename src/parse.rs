@@ -0,0 +1,418 @@
+// turns yt-dlp chat lines into ChatEvents
+
+use crate::currency;
+use crate::event::*;
+use crate::youtube::{self, Action, ChatItem, ChatItemType, RunsContainer, RunsTypes};
+use std::collections::VecDeque;
+use std::io::BufRead;
+
+/// Why a line could not be turned into events.
+#[derive(Debug)]
+pub enum Error {
+	/// The line isn't json or doesn't look like a chat line.
+	Json(serde_json::Error),
+	/// Reading from the underlying reader failed.
+	Io(std::io::Error),
+	/// A timestampUsec that isn't a number.
+	InvalidTimestamp(String),
+}
+
+impl std::fmt::Display for Error {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Error::Json(e) => write!(f, "{e}"),
+			Error::Io(e) => write!(f, "{e}"),
+			Error::InvalidTimestamp(usec) => write!(f, "invalid timestampUsec {usec:?}"),
+		}
+	}
+}
+
+impl std::error::Error for Error {}
+
+impl From<serde_json::Error> for Error {
+	fn from(e: serde_json::Error) -> Error {
+		Error::Json(e)
+	}
+}
+
+impl From<std::io::Error> for Error {
+	fn from(e: std::io::Error) -> Error {
+		Error::Io(e)
+	}
+}
+
+/// Parses one line of a yt-dlp `.live_chat.json` file.
+///
+/// Returns the events in the order youtube sent them, which can be none for lines that only
+/// change things like banners.
+///
+/// ```
+/// let line = r#"{"replayChatItemAction": {"actions": [{"removeChatItemAction": {"targetItemId": "abc"}}]}, "videoOffsetTimeMsec": "5000"}"#;
+/// let events = superchat_extractor::parse_line(line).unwrap();
+/// match &events[0] {
+///     superchat_extractor::ChatEvent::MessageDeleted(deleted) => {
+///         assert_eq!(deleted.target_id, "abc");
+///         assert_eq!(deleted.video_offset_ms, Some(5000));
+///     },
+///     other => panic!("unexpected event {other:?}"),
+/// }
+/// ```
+pub fn parse_line(line: &str) -> Result<Vec<ChatEvent>, Error> {
+	let chat_item: ChatItem = serde_json::from_str(line)?;
+	// every action in a line happened at the same point in the video
+	let video_offset_ms = chat_item.videoOffsetTimeMsec
+		.or(chat_item.replayChatItemAction.videoOffsetTimeMsec)
+		.and_then(|offset| offset.parse::<i64>().ok());
+
+	let mut events = Vec::new();
+	for action in chat_item.replayChatItemAction.actions {
+		let event = match action {
+			Action::AddChatItem { addChatItemAction, .. } => match chat_item_event(addChatItemAction.item, video_offset_ms)? {
+				Some(event) => event,
+				None => continue,
+			},
+			Action::RemoveChatItem { removeChatItemAction, .. } => ChatEvent::MessageDeleted(MessageDeleted {
+				target_id: removeChatItemAction.targetItemId,
+				video_offset_ms,
+			}),
+			Action::RemoveChatItemByAuthor { removeChatItemByAuthorAction, .. } => ChatEvent::AuthorRemoved(AuthorRemoved {
+				channel_id: removeChatItemByAuthorAction.externalChannelId,
+				video_offset_ms,
+			}),
+			Action::UpdateLiveChatPoll { updateLiveChatPollAction, .. } => {
+				let poll = updateLiveChatPollAction.pollToUpdate.pollRenderer;
+				ChatEvent::PollUpdate(Poll {
+					id: poll.liveChatPollId,
+					question: message(poll.header.pollHeaderRenderer.pollQuestion),
+					metadata: message(poll.header.pollHeaderRenderer.metadataText),
+					choices: poll.choices.into_iter().map(|choice| PollChoice {
+						text: message(choice.text),
+						vote_percentage: choice.votePercentage.simpleText,
+					}).collect(),
+				})
+			},
+			// banners, the ticker at the top and panels don't say anything about the chat itself
+			Action::AddBannerToLiveChatCommand { .. }
+			| Action::AddLiveChatTickerItem { .. }
+			| Action::CloseLiveChatActionPanel { .. }
+			| Action::LiveChatReportModerationStateCommand { .. }
+			| Action::RemoveBannerForLiveChatCommand { .. }
+			| Action::ReplaceChatItem { .. }
+			| Action::ShowLiveChatActionPanel { .. }
+			| Action::ReplaceLiveChatRenderer { .. } => continue,
+			Action::Unknown(value) => ChatEvent::Unknown(Unknown { kind: "action", value }),
+		};
+		events.push(event);
+	}
+	Ok(events)
+}
+
+fn chat_item_event(item: ChatItemType, video_offset_ms: Option<i64>) -> Result<Option<ChatEvent>, Error> {
+	let event = match item {
+		ChatItemType::LiveChatPaidMessageRenderer { liveChatPaidMessageRenderer: paid } => ChatEvent::Superchat(Superchat {
+			time: time(&paid.timestampUsec, paid.timestampText, video_offset_ms)?,
+			author: author(paid.authorExternalChannelId, paid.authorName, paid.authorPhoto, paid.authorBadges),
+			id: paid.id,
+			amount: currency::parse_amount(&paid.purchaseAmountText.simpleText),
+			amount_text: paid.purchaseAmountText.simpleText,
+			message: paid.message.map(message),
+			header_color: paid.headerBackgroundColor,
+			body_color: paid.bodyBackgroundColor,
+		}),
+		ChatItemType::LiveChatPaidStickerRenderer { liveChatPaidStickerRenderer: sticker } => ChatEvent::Sticker(Sticker {
+			time: time(&sticker.timestampUsec, sticker.timestampText, video_offset_ms)?,
+			author: author(sticker.authorExternalChannelId, sticker.authorName, sticker.authorPhoto, None),
+			id: sticker.id,
+			amount: currency::parse_amount(&sticker.purchaseAmountText.simpleText),
+			amount_text: sticker.purchaseAmountText.simpleText,
+			image_url: sticker.sticker.thumbnails.last().map(|thumbnail| thumbnail.url.clone()),
+			description: sticker.sticker.accessibility.accessibilityData.label,
+			background_color: sticker.backgroundColor,
+		}),
+		ChatItemType::LiveChatMembershipItemRenderer { liveChatMembershipItemRenderer: membership } => ChatEvent::Membership(Membership {
+			time: time(&membership.timestampUsec, membership.timestampText, video_offset_ms)?,
+			author: author(membership.authorExternalChannelId, membership.authorName, membership.authorPhoto, membership.authorBadges),
+			id: membership.id,
+			// new members only get a welcome in headerSubtext
+			months: membership.headerPrimaryText.map(message),
+			message: membership.message.map(message),
+		}),
+		ChatItemType::LiveChatSponsorshipsGiftPurchaseAnnouncementRenderer { liveChatSponsorshipsGiftPurchaseAnnouncementRenderer: gift } => {
+			let header = gift.header.liveChatSponsorshipsHeaderRenderer;
+			// "Gifted ", "5", " memberships"
+			let number = match header.primaryText.runs.get(1) {
+				Some(RunsTypes::Text { text, .. }) => Some(text.clone()),
+				_ => None,
+			};
+			ChatEvent::GiftPurchase(GiftPurchase {
+				// gifts never have a timestampText
+				time: time(&gift.timestampUsec, None, video_offset_ms)?,
+				author: author(gift.authorExternalChannelId, header.authorName, header.authorPhoto, header.authorBadges),
+				id: gift.id,
+				number,
+			})
+		},
+		ChatItemType::LiveChatSponsorshipsGiftRedemptionAnnouncementRenderer { liveChatSponsorshipsGiftRedemptionAnnouncementRenderer: redemption } => {
+			// "received a gift membership by ", "sender"
+			let sender = match redemption.message.runs.get(1) {
+				Some(RunsTypes::Text { text, .. }) => Some(text.clone()),
+				_ => None,
+			};
+			ChatEvent::GiftRedemption(GiftRedemption {
+				time: time(&redemption.timestampUsec, redemption.timestampText, video_offset_ms)?,
+				author: author(redemption.authorExternalChannelId, redemption.authorName, redemption.authorPhoto, None),
+				id: redemption.id,
+				message: message(redemption.message),
+				sender,
+			})
+		},
+		ChatItemType::LiveChatTextMessageRenderer { liveChatTextMessageRenderer: text } => ChatEvent::TextMessage(TextMessage {
+			time: time(&text.timestampUsec, text.timestampText, video_offset_ms)?,
+			author: author(text.authorExternalChannelId, text.authorName, text.authorPhoto, text.authorBadges),
+			id: text.id,
+			message: message(text.message),
+		}),
+		// placeholders, subscriber only mode notices and slow mode changes
+		ChatItemType::LiveChatPlaceholderItemRenderer { .. }
+		| ChatItemType::LiveChatViewerEngagementMessageRenderer { .. }
+		| ChatItemType::LiveChatModeChangeMessageRenderer { .. } => return Ok(None),
+		ChatItemType::Unknown(value) => ChatEvent::Unknown(Unknown { kind: "chat_item", value }),
+	};
+	Ok(Some(event))
+}
+
+fn time(usec: &str, timestamp_text: Option<youtube::SimpleText>, video_offset_ms: Option<i64>) -> Result<EventTime, Error> {
+	Ok(EventTime {
+		timestamp_usec: usec.parse::<i64>().map_err(|_| Error::InvalidTimestamp(usec.to_string()))?,
+		timestamp_text: timestamp_text.map(|text| text.simpleText),
+		video_offset_ms,
+	})
+}
+
+fn author(channel_id: String, name: youtube::SimpleText, photo: youtube::AuthorPhotos, badges: Option<Vec<youtube::AuthorBadges>>) -> Author {
+	Author {
+		channel_id,
+		name: name.simpleText,
+		// thumbnails go from smallest to largest
+		photo_url: photo.thumbnails.last().map(|thumbnail| thumbnail.url.clone()),
+		// badges are things like moderator, verified and membership duration
+		badges: badges.unwrap_or_default()
+			.into_iter()
+			.map(|badge| badge.liveChatAuthorBadgeRenderer.tooltip)
+			.collect(),
+	}
+}
+
+fn message(runs: RunsContainer) -> Message {
+	let mut message = Message::default();
+	for run in runs.runs {
+		match run {
+			RunsTypes::Text { text, .. } => message.text.push_str(&text),
+			RunsTypes::Emoji { emoji } => {
+				if emoji.isCustomEmoji == Some(true) {
+					let label = emoji.image.accessibility.accessibilityData.label;
+					message.text.push_str(&format!(":{label}:"));
+					message.emojis.push(CustomEmoji {
+						label,
+						image_url: emoji.image.thumbnails.last().map(|thumbnail| thumbnail.url.clone()),
+					});
+				} else {
+					message.text.push_str(&emoji.emojiId);
+				}
+			},
+			RunsTypes::Unknown(value) => message.unknown_runs.push(value),
+		}
+	}
+	message
+}
+
+/// Reads chat events from a yt-dlp `.live_chat.json` file, or anything else with one chat line per line.
+///
+/// A line that fails to parse gives an error and reading carries on with the next line,
+/// so stop at the first error or skip it, whichever suits.
+///
+/// ```no_run
+/// let file = std::fs::File::open("stream.live_chat.json").unwrap();
+/// for event in superchat_extractor::ChatReader::new(std::io::BufReader::new(file)) {
+///     if let superchat_extractor::ChatEvent::Superchat(superchat) = event.unwrap() {
+///         println!("{} sent {}", superchat.author.name, superchat.amount_text);
+///     }
+/// }
+/// ```
+pub struct ChatReader<R> {
+	reader: R,
+	line: String,
+	line_number: usize,
+	pending: VecDeque<ChatEvent>,
+}
+
+impl<R: BufRead> ChatReader<R> {
+	pub fn new(reader: R) -> ChatReader<R> {
+		ChatReader { reader, line: String::new(), line_number: 0, pending: VecDeque::new() }
+	}
+
+	/// The line the last event or error came from, starting at 1.
+	pub fn line_number(&self) -> usize {
+		self.line_number
+	}
+}
+
+impl<R: BufRead> Iterator for ChatReader<R> {
+	type Item = Result<ChatEvent, Error>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			if let Some(event) = self.pending.pop_front() {
+				return Some(Ok(event));
+			}
+			self.line.clear();
+			match self.reader.read_line(&mut self.line) {
+				Ok(0) => return None,
+				Ok(_) => {},
+				Err(e) => return Some(Err(e.into())),
+			}
+			self.line_number += 1;
+			// a file that ends with an empty line is still fine
+			if self.line.trim().is_empty() {
+				continue;
+			}
+			match parse_line(&self.line) {
+				Ok(events) => self.pending.extend(events),
+				Err(e) => return Some(Err(e)),
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::currency::Amount;
+
+	const TEXT_MESSAGE: &str = r#"{"replayChatItemAction": {"actions": [{"addChatItemAction": {"item": {"liveChatTextMessageRenderer": {"contextMenuAccessibility": {}, "contextMenuEndpoint": {}, "authorExternalChannelId": "UCaaa", "authorName": {"simpleText": "Alice"}, "authorPhoto": {"thumbnails": [{"url": "https://yt/a32"}, {"url": "https://yt/a64"}]}, "authorBadges": [{"liveChatAuthorBadgeRenderer": {"accessibility": {"accessibilityData": {"label": "Moderator"}}, "tooltip": "Moderator"}}], "id": "m1", "message": {"runs": [{"text": "hi "}, {"emoji": {"emojiId": "UCx/abc", "image": {"accessibility": {"accessibilityData": {"label": "wave"}}, "thumbnails": [{"url": "https://yt/wave"}]}, "isCustomEmoji": true}}, {"emoji": {"emojiId": "👍", "image": {"accessibility": {"accessibilityData": {"label": "thumbs up"}}, "thumbnails": []}}}]}, "timestampText": {"simpleText": "1:05"}, "timestampUsec": "1700000065000000"}}}}], "videoOffsetTimeMsec": "65000"}}"#;
+
+	const SUPERCHAT: &str = r#"{"replayChatItemAction": {"actions": [{"addChatItemAction": {"item": {"liveChatPaidMessageRenderer": {"authorExternalChannelId": "UCbbb", "authorName": {"simpleText": "Bob"}, "authorNameTextColor": 1, "authorPhoto": {"thumbnails": [{"url": "https://yt/a32", "width": 32, "height": 32}, {"url": "https://yt/a64", "width": 64, "height": 64}]}, "bodyBackgroundColor": 4280191205, "bodyTextColor": 1, "headerBackgroundColor": 4278248959, "headerTextColor": 1, "id": "p1", "message": {"runs": [{"text": "great stream, \"thanks\"\nbye"}]}, "purchaseAmountText": {"simpleText": "$5.00"}, "textInputBackgroundColor": 1, "timestampText": {"simpleText": "0:05"}, "timestampColor": 1, "timestampUsec": "1684000005000000"}}, "clientId": null}}]}, "videoOffsetTimeMsec": "5000"}"#;
+	const STICKER: &str = r#"{"replayChatItemAction": {"actions": [{"addChatItemAction": {"item": {"liveChatPaidStickerRenderer": {"contextMenuAccessibility": {}, "contextMenuEndpoint": {}, "authorExternalChannelId": "UCfff", "authorName": {"simpleText": "Fay"}, "authorNameTextColor": 1, "authorPhoto": {"thumbnails": [{"url": "https://yt/a32", "width": 32, "height": 32}, {"url": "https://yt/a64", "width": 64, "height": 64}]}, "backgroundColor": 4294947584, "id": "s1", "moneyChipBackgroundColor": 1, "moneyChipTextColor": 1, "purchaseAmountText": {"simpleText": "CA$10.00"}, "sticker": {"accessibility": {"accessibilityData": {"label": "cat dancing"}}, "thumbnails": [{"url": "https://yt/sticker"}]}, "stickerDisplayHeight": 1, "stickerDisplayWidth": 1, "timestampText": {"simpleText": "0:40"}, "timestampUsec": "1684000040000000"}}, "clientId": null}}]}, "videoOffsetTimeMsec": "40000"}"#;
+	const MEMBERSHIP: &str = r#"{"replayChatItemAction": {"actions": [{"addChatItemAction": {"item": {"liveChatMembershipItemRenderer": {"contextMenuAccessibility": {}, "contextMenuEndpoint": {}, "authorExternalChannelId": "UCddd", "authorName": {"simpleText": "Dan"}, "authorPhoto": {"thumbnails": [{"url": "https://yt/a32", "width": 32, "height": 32}, {"url": "https://yt/a64", "width": 64, "height": 64}]}, "headerPrimaryText": {"runs": [{"text": "Member for "}, {"text": "3"}, {"text": " months"}]}, "headerSubtext": {"simpleText": "The Club"}, "id": "ms1", "message": {"runs": [{"text": "3 months!"}]}, "timestampText": {"simpleText": "0:20"}, "timestampUsec": "1684000020000000"}}, "clientId": null}}]}, "videoOffsetTimeMsec": "20000"}"#;
+	const GIFT_PURCHASE: &str = r#"{"replayChatItemAction": {"actions": [{"addChatItemAction": {"item": {"liveChatSponsorshipsGiftPurchaseAnnouncementRenderer": {"authorExternalChannelId": "UCbbb", "id": "g1", "timestampUsec": "1684000030000000", "header": {"liveChatSponsorshipsHeaderRenderer": {"contextMenuAccessibility": {}, "contextMenuEndpoint": {}, "authorName": {"simpleText": "Bob"}, "authorPhoto": {"thumbnails": [{"url": "https://yt/a32", "width": 32, "height": 32}, {"url": "https://yt/a64", "width": 64, "height": 64}]}, "image": {}, "primaryText": {"runs": [{"text": "Gifted "}, {"text": "5"}, {"text": " memberships"}]}}}}}, "clientId": null}}]}, "videoOffsetTimeMsec": "30000"}"#;
+	const GIFT_REDEMPTION: &str = r#"{"replayChatItemAction": {"actions": [{"addChatItemAction": {"item": {"liveChatSponsorshipsGiftRedemptionAnnouncementRenderer": {"contextMenuAccessibility": {}, "contextMenuEndpoint": {}, "authorExternalChannelId": "UCeee", "authorName": {"simpleText": "Eve"}, "authorPhoto": {"thumbnails": [{"url": "https://yt/a32", "width": 32, "height": 32}, {"url": "https://yt/a64", "width": 64, "height": 64}]}, "id": "r1", "message": {"runs": [{"text": "received a gift membership by "}, {"text": "Bob"}]}, "timestampText": {"simpleText": "0:31"}, "timestampUsec": "1684000031000000"}}, "clientId": null}}]}, "videoOffsetTimeMsec": "31000"}"#;
+	const POLL_UPDATE: &str = r#"{"replayChatItemAction": {"actions": [{"updateLiveChatPollAction": {"pollToUpdate": {"pollRenderer": {"liveChatPollId": "poll1", "choices": [{"selected": false, "text": {"runs": [{"text": "Yes"}]}, "votePercentage": {"simpleText": "60%"}, "voteRatio": 0.6}, {"selected": false, "text": {"runs": [{"text": "No"}]}, "votePercentage": {"simpleText": "40%"}, "voteRatio": 0.4}], "header": {"pollHeaderRenderer": {"pollQuestion": {"runs": [{"text": "Continue?"}]}, "metadataText": {"runs": [{"text": "Bob • 10 votes"}]}, "liveChatPollType": "x"}}}}}}]}, "videoOffsetTimeMsec": "70000"}"#;
+	const MESSAGE_DELETED: &str = r#"{"replayChatItemAction": {"actions": [{"removeChatItemAction": {"targetItemId": "m2"}}]}, "videoOffsetTimeMsec": "60000"}"#;
+	const AUTHOR_REMOVED: &str = r#"{"replayChatItemAction": {"actions": [{"removeChatItemByAuthorAction": {"externalChannelId": "UCbbb"}}]}, "videoOffsetTimeMsec": "61000"}"#;
+
+	fn only_event(line: &str) -> ChatEvent {
+		let mut events = parse_line(line).unwrap();
+		assert_eq!(events.len(), 1, "{events:?}");
+		events.remove(0)
+	}
+
+	#[test]
+	fn superchat() {
+		let ChatEvent::Superchat(superchat) = only_event(SUPERCHAT) else { panic!("not a superchat") };
+		assert_eq!(superchat.id, "p1");
+		assert_eq!(superchat.author.channel_id, "UCbbb");
+		assert_eq!(superchat.amount_text, "$5.00");
+		assert_eq!(superchat.amount, Some(Amount { currency: "USD".to_string(), minor_units: 500 }));
+		assert_eq!((superchat.header_color, superchat.body_color), (4278248959, 4280191205));
+		assert_eq!(superchat.message.unwrap().text, "great stream, \"thanks\"\nbye");
+		assert_eq!(superchat.time.video_offset_ms, Some(5000));
+	}
+
+	#[test]
+	fn sticker() {
+		let ChatEvent::Sticker(sticker) = only_event(STICKER) else { panic!("not a sticker") };
+		assert_eq!(sticker.author.channel_id, "UCfff");
+		assert_eq!(sticker.amount, Some(Amount { currency: "CAD".to_string(), minor_units: 1000 }));
+		assert_eq!(sticker.background_color, 4294947584);
+		assert_eq!(sticker.description, "cat dancing");
+		assert_eq!(sticker.image_url.as_deref(), Some("https://yt/sticker"));
+	}
+
+	#[test]
+	fn membership() {
+		let ChatEvent::Membership(membership) = only_event(MEMBERSHIP) else { panic!("not a membership") };
+		assert_eq!(membership.author.channel_id, "UCddd");
+		assert_eq!(membership.months.unwrap().text, "Member for 3 months");
+		assert_eq!(membership.message.unwrap().text, "3 months!");
+	}
+
+	#[test]
+	fn gift_purchase() {
+		// the author's name and photo are in the header, only the channel id is on the renderer itself
+		let ChatEvent::GiftPurchase(gift) = only_event(GIFT_PURCHASE) else { panic!("not a gift purchase") };
+		assert_eq!(gift.author.channel_id, "UCbbb");
+		assert_eq!(gift.author.name, "Bob");
+		assert_eq!(gift.number.as_deref(), Some("5"));
+		// no timestampText on these
+		assert_eq!(gift.time.timestamp_text, None);
+	}
+
+	#[test]
+	fn gift_redemption() {
+		let ChatEvent::GiftRedemption(redemption) = only_event(GIFT_REDEMPTION) else { panic!("not a gift redemption") };
+		assert_eq!(redemption.author.channel_id, "UCeee");
+		assert_eq!(redemption.sender.as_deref(), Some("Bob"));
+	}
+
+	#[test]
+	fn poll_update() {
+		let ChatEvent::PollUpdate(poll) = only_event(POLL_UPDATE) else { panic!("not a poll") };
+		assert_eq!(poll.id, "poll1");
+		assert_eq!(poll.question.text, "Continue?");
+		assert_eq!(poll.metadata.text, "Bob • 10 votes");
+		let choices: Vec<(&str, &str)> = poll.choices.iter().map(|choice| (choice.text.text.as_str(), choice.vote_percentage.as_str())).collect();
+		assert_eq!(choices, [("Yes", "60%"), ("No", "40%")]);
+	}
+
+	#[test]
+	fn removals() {
+		let ChatEvent::MessageDeleted(deleted) = only_event(MESSAGE_DELETED) else { panic!("not a deletion") };
+		assert_eq!((deleted.target_id.as_str(), deleted.video_offset_ms), ("m2", Some(60000)));
+		let ChatEvent::AuthorRemoved(removed) = only_event(AUTHOR_REMOVED) else { panic!("not an author removal") };
+		assert_eq!((removed.channel_id.as_str(), removed.video_offset_ms), ("UCbbb", Some(61000)));
+	}
+
+	#[test]
+	fn text_message() {
+		let events = parse_line(TEXT_MESSAGE).unwrap();
+		assert_eq!(events.len(), 1);
+		let ChatEvent::TextMessage(message) = &events[0] else { panic!("not a text message: {:?}", events[0]) };
+		assert_eq!(message.id, "m1");
+		assert_eq!(message.author.channel_id, "UCaaa");
+		assert_eq!(message.author.name, "Alice");
+		assert_eq!(message.author.photo_url.as_deref(), Some("https://yt/a64"));
+		assert_eq!(message.author.badges, vec!["Moderator".to_string()]);
+		assert_eq!(message.message.text, "hi :wave:👍");
+		assert_eq!(message.message.emojis.len(), 1);
+		assert_eq!(message.message.emojis[0].image_url.as_deref(), Some("https://yt/wave"));
+		assert_eq!(message.time.timestamp_usec, 1_700_000_065_000_000);
+		assert_eq!(message.time.timestamp_text.as_deref(), Some("1:05"));
+		assert_eq!(message.time.video_offset_ms, Some(65000));
+	}
+
+	#[test]
+	fn ignored_and_unknown_actions() {
+		let line = r#"{"replayChatItemAction": {"actions": [{"addBannerToLiveChatCommand": {}}, {"somethingNew": {"a": 1}}]}}"#;
+		let events = parse_line(line).unwrap();
+		assert_eq!(events.len(), 1);
+		let ChatEvent::Unknown(unknown) = &events[0] else { panic!("not unknown: {:?}", events[0]) };
+		assert_eq!(unknown.kind, "action");
+	}
+
+	#[test]
+	fn invalid_line() {
+		assert!(matches!(parse_line("{"), Err(Error::Json(_))));
+	}
+
+	#[test]
+	fn reader_carries_on_after_errors() {
+		let input = format!("{TEXT_MESSAGE}\nnot json\n\n{TEXT_MESSAGE}\n");
+		let mut reader = ChatReader::new(input.as_bytes());
+		assert!(matches!(reader.next(), Some(Ok(ChatEvent::TextMessage(_)))));
+		assert!(matches!(reader.next(), Some(Err(Error::Json(_)))));
+		assert_eq!(reader.line_number(), 2);
+		assert!(matches!(reader.next(), Some(Ok(ChatEvent::TextMessage(_)))));
+		assert_eq!(reader.line_number(), 4);
+		assert!(reader.next().is_none());
+	}
+}
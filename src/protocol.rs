@@ -0,0 +1,176 @@
+// what --live clients and the serve subcommand say to each other
+//
+// every message is one json object on its own line, so a message ends at the newline
+// json strings escape newlines so they can never show up inside one
+// every message carries the protocol version and its type:
+//
+//   {"v":1,"type":"hello","client":"superchat-extractor 0.1.0"}
+//   {"v":1,"type":"stream_start","stream":"Title [id]","metadata":{"video_id":"id","title":"Title","channel":null,"channel_id":null,"release_timestamp":null}}
+//   {"v":1,"type":"event","stream":"Title [id]","seq":0,"event":{"type":"Donation",...}}
+//   {"v":1,"type":"heartbeat"}
+//   {"v":1,"type":"stream_end","stream":"Title [id]","events":1}
+//
+// a client starts every connection with hello and then stream_start for the stream it's sending
+// the stream is named after the chat file, metadata is whatever could be found about it and may be left out
+// events are the same objects as the lines of the output file, seq counts up from 0 per stream
+// so a gap means events were lost and a repeat means they were sent again after a reconnect
+// heartbeats are sent while the chat is quiet so the server knows the client is still there
+// stream_end is sent once the chat file is finished, a connection closing without it means the client went away
+// the server drops the connection when the version isn't one it knows
+
+use crate::metadata::StreamMetadata;
+use superchat_extractor::export::ExportStructs;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+pub const VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[allow(clippy::large_enum_variant)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+	Hello { client: String },
+	StreamStart {
+		stream: String,
+		#[serde(default)]
+		metadata: StreamMetadata,
+	},
+	Event { stream: String, seq: u64, event: ExportStructs },
+	Heartbeat,
+	StreamEnd { stream: String, events: u64 },
+}
+
+#[derive(Serialize)]
+struct OutgoingFrame<'a> {
+	v: u32,
+	#[serde(flatten)]
+	message: &'a Message,
+}
+
+#[derive(Deserialize)]
+struct IncomingFrame {
+	#[serde(flatten)]
+	message: Message,
+}
+
+// only the version, read first so a newer message doesn't get reported as garbage
+#[derive(Deserialize)]
+struct Version {
+	v: u32,
+}
+
+#[derive(Debug)]
+pub enum DecodeError {
+	UnsupportedVersion(u32),
+	Invalid(serde_json::Error),
+}
+
+impl fmt::Display for DecodeError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			DecodeError::UnsupportedVersion(v) => write!(f, "unsupported protocol version {v}, this side speaks {VERSION}"),
+			DecodeError::Invalid(e) => write!(f, "invalid message: {e}"),
+		}
+	}
+}
+
+// one line without the newline
+pub fn encode(message: &Message) -> String {
+	serde_json::to_string(&OutgoingFrame { v: VERSION, message }).expect("could not serialize message")
+}
+
+pub fn decode(line: &str) -> Result<Message, DecodeError> {
+	let version: Version = serde_json::from_str(line).map_err(DecodeError::Invalid)?;
+	if version.v != VERSION {
+		return Err(DecodeError::UnsupportedVersion(version.v));
+	}
+	let frame: IncomingFrame = serde_json::from_str(line).map_err(DecodeError::Invalid)?;
+	Ok(frame.message)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const DONATION: &str = r#"{"type":"Donation","username":"someone","channel_id":"UCaaa","amount":"$5.00","currency":"USD","amount_value":5.0,"message":"hi\nthere","time":"0:01:02","header_color":4278239141,"body_color":4280150454,"thumbnail_url":"https://example.com/a.png"}"#;
+
+	fn round_trip(message: &Message) -> Message {
+		let line = encode(message);
+		assert!(!line.contains('\n'), "message spans more than one line: {line}");
+		decode(&line).expect("could not decode message")
+	}
+
+	fn event() -> ExportStructs {
+		serde_json::from_str(DONATION).expect("could not parse donation")
+	}
+
+	#[test]
+	fn hello() {
+		let message = round_trip(&Message::Hello { client: "superchat-extractor 0.1.0".to_string() });
+		assert!(matches!(message, Message::Hello { client } if client == "superchat-extractor 0.1.0"));
+	}
+
+	#[test]
+	fn stream_start() {
+		let metadata = StreamMetadata { video_id: Some("dQw4w9WgXcQ".to_string()), title: Some("Title".to_string()), release_timestamp: Some(1700000000), ..Default::default() };
+		let message = round_trip(&Message::StreamStart { stream: "Title [dQw4w9WgXcQ]".to_string(), metadata });
+		let Message::StreamStart { stream, metadata } = message else {
+			panic!("not a stream start: {message:?}");
+		};
+		assert_eq!(stream, "Title [dQw4w9WgXcQ]");
+		assert_eq!(metadata.video_id.as_deref(), Some("dQw4w9WgXcQ"));
+		assert_eq!(metadata.title.as_deref(), Some("Title"));
+		assert_eq!(metadata.channel, None);
+		assert_eq!(metadata.release_timestamp, Some(1700000000));
+	}
+
+	#[test]
+	fn stream_start_without_metadata() {
+		let message = decode(r#"{"v":1,"type":"stream_start","stream":"Title [id]"}"#).expect("could not decode message");
+		assert!(matches!(message, Message::StreamStart { metadata: StreamMetadata { video_id: None, .. }, .. }));
+	}
+
+	#[test]
+	fn event_keeps_the_export_object() {
+		let message = round_trip(&Message::Event { stream: "Title [id]".to_string(), seq: 7, event: event() });
+		let Message::Event { stream, seq, event } = message else {
+			panic!("not an event: {message:?}");
+		};
+		assert_eq!(stream, "Title [id]");
+		assert_eq!(seq, 7);
+		let expected: serde_json::Value = serde_json::from_str(DONATION).unwrap();
+		let actual = serde_json::to_value(&event).unwrap();
+		for (key, value) in expected.as_object().unwrap() {
+			assert_eq!(&actual[key], value, "{key} changed");
+		}
+	}
+
+	#[test]
+	fn heartbeat() {
+		assert!(matches!(round_trip(&Message::Heartbeat), Message::Heartbeat));
+	}
+
+	#[test]
+	fn stream_end() {
+		let message = round_trip(&Message::StreamEnd { stream: "Title [id]".to_string(), events: 3 });
+		assert!(matches!(message, Message::StreamEnd { stream, events: 3 } if stream == "Title [id]"));
+	}
+
+	#[test]
+	fn envelope_has_version_and_type() {
+		let line = encode(&Message::Heartbeat);
+		assert_eq!(line, r#"{"v":1,"type":"heartbeat"}"#);
+	}
+
+	#[test]
+	fn other_versions_are_rejected() {
+		let line = r#"{"v":2,"type":"heartbeat"}"#;
+		assert!(matches!(decode(line), Err(DecodeError::UnsupportedVersion(2))));
+	}
+
+	#[test]
+	fn old_bare_events_are_rejected() {
+		assert!(matches!(decode(DONATION), Err(DecodeError::Invalid(_))));
+		assert!(matches!(decode("Title [id]"), Err(DecodeError::Invalid(_))));
+	}
+}
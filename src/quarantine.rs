@@ -0,0 +1,60 @@
+// lines that could not be parsed, for --skip-invalid and --quarantine
+// the line number and error are kept for the summary, the line itself goes to the quarantine file as is
+
+use std::io::Write;
+
+pub struct InvalidLines<W: Write> {
+	// line number and error
+	pub lines: Vec<(usize, String)>,
+	quarantine: Option<W>,
+}
+
+impl<W: Write> InvalidLines<W> {
+	pub fn new(quarantine: Option<W>) -> InvalidLines<W> {
+		InvalidLines { lines: Vec::new(), quarantine }
+	}
+
+	pub fn record(&mut self, line_number: usize, line: &str, error: &dyn std::fmt::Display) -> std::io::Result<()> {
+		if let Some(quarantine) = self.quarantine.as_mut() {
+			writeln!(quarantine, "{line}")?;
+		}
+		self.lines.push((line_number, error.to_string()));
+		Ok(())
+	}
+
+	// flushes the quarantine file and hands back the lines for the summary
+	pub fn finish(mut self) -> std::io::Result<Vec<(usize, String)>> {
+		if let Some(quarantine) = self.quarantine.as_mut() {
+			quarantine.flush()?;
+		}
+		Ok(self.lines)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn keeps_line_numbers_and_quarantines_the_raw_lines() {
+		let chat = "{\"replayChatItemAction\": {\"actions\": []}}\nnot json\n{\"replayChatItemAction\": {\"actions\": []}}\n{\"truncated\":";
+		let mut invalid = InvalidLines::new(Some(Vec::new()));
+		for (index, line) in chat.lines().enumerate() {
+			if let Err(e) = superchat_extractor::parse_line(line) {
+				invalid.record(index + 1, line, &e).unwrap();
+			}
+		}
+		let quarantine = invalid.quarantine.take().unwrap();
+		let lines = invalid.finish().unwrap();
+		assert_eq!(lines.iter().map(|(number, _)| *number).collect::<Vec<_>>(), vec![2, 4]);
+		assert!(lines[0].1.contains("expected"), "{}", lines[0].1);
+		assert_eq!(String::from_utf8(quarantine).unwrap(), "not json\n{\"truncated\":\n");
+	}
+
+	#[test]
+	fn works_without_a_quarantine_file() {
+		let mut invalid: InvalidLines<Vec<u8>> = InvalidLines::new(None);
+		invalid.record(7, "oops", &"bad line").unwrap();
+		assert_eq!(invalid.finish().unwrap(), vec![(7, "bad line".to_string())]);
+	}
+}
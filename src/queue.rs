@@ -0,0 +1,432 @@
+// terminal ui for reading superchats on stream
+// read state is saved next to the chat file so a reading session can be picked up later
+
+use crate::filter;
+use crate::rates::RateTable;
+use superchat_extractor::currency::Amount;
+use superchat_extractor::export::ExportStructs;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+	#[default]
+	Unread,
+	Read,
+	Skipped,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy)]
+struct ItemState {
+	status: Status,
+	starred: bool,
+}
+
+// keyed by youtube's id of the event, so it still fits when the file is processed again
+// with different filters or yt-dlp appended to it
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct SavedState {
+	items: BTreeMap<String, ItemState>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum TypeFilter {
+	All,
+	Superchats,
+	Stickers,
+	Memberships,
+}
+
+impl TypeFilter {
+	fn next(self) -> TypeFilter {
+		match self {
+			TypeFilter::All => TypeFilter::Superchats,
+			TypeFilter::Superchats => TypeFilter::Stickers,
+			TypeFilter::Stickers => TypeFilter::Memberships,
+			TypeFilter::Memberships => TypeFilter::All,
+		}
+	}
+
+	fn name(self) -> &'static str {
+		match self {
+			TypeFilter::All => "everything",
+			TypeFilter::Superchats => "superchats",
+			TypeFilter::Stickers => "stickers",
+			TypeFilter::Memberships => "memberships",
+		}
+	}
+
+	fn matches(self, event: &ExportStructs) -> bool {
+		match self {
+			TypeFilter::All => true,
+			TypeFilter::Superchats => matches!(event, ExportStructs::Donation(_)),
+			TypeFilter::Stickers => matches!(event, ExportStructs::Sticker(_)),
+			TypeFilter::Memberships => matches!(event, ExportStructs::Membership(_) | ExportStructs::GiftingMembership(_) | ExportStructs::GiftMembership(_)),
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum View {
+	Unread,
+	Starred,
+	All,
+}
+
+impl View {
+	fn next(self) -> View {
+		match self {
+			View::Unread => View::Starred,
+			View::Starred => View::All,
+			View::All => View::Unread,
+		}
+	}
+
+	fn name(self) -> &'static str {
+		match self {
+			View::Unread => "unread",
+			View::Starred => "starred",
+			View::All => "all",
+		}
+	}
+}
+
+struct Queue<'a> {
+	events: Vec<ExportStructs>,
+	state: SavedState,
+	state_path: String,
+	type_filter: TypeFilter,
+	view: View,
+	// in the base currency, compared like --min-amount
+	min_amount: f64,
+	base_currency: String,
+	rate_table: Option<&'a RateTable>,
+	// positions in events of what is currently shown
+	visible: Vec<usize>,
+	list: ListState,
+}
+
+pub fn run(events: Vec<ExportStructs>, state_path: &str, base_currency: &str, rate_table: Option<&RateTable>) -> std::io::Result<()> {
+	// normal chat isn't part of the queue
+	let events: Vec<ExportStructs> = events.into_iter().filter(|event| !matches!(event, ExportStructs::TextMessage(_))).collect();
+	let state = match std::fs::read_to_string(state_path) {
+		Ok(contents) => serde_json::from_str(&contents).map_err(std::io::Error::other)?,
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => SavedState::default(),
+		Err(e) => return Err(e),
+	};
+
+	let mut queue = Queue {
+		events,
+		state,
+		state_path: state_path.to_string(),
+		type_filter: TypeFilter::All,
+		view: View::Unread,
+		min_amount: 0.0,
+		base_currency: base_currency.to_uppercase(),
+		rate_table,
+		visible: Vec::new(),
+		list: ListState::default(),
+	};
+	queue.refresh(None);
+
+	let mut terminal = ratatui::init();
+	let result = queue.event_loop(&mut terminal);
+	ratatui::restore();
+	result
+}
+
+impl Queue<'_> {
+	fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+		loop {
+			terminal.draw(|frame| self.draw(frame))?;
+			let Event::Key(key) = event::read()? else {
+				continue;
+			};
+			if key.kind != KeyEventKind::Press {
+				continue;
+			}
+			match key.code {
+				KeyCode::Char('q') | KeyCode::Esc => return self.save(),
+				KeyCode::Down | KeyCode::Char('j') => self.list.select_next(),
+				KeyCode::Up | KeyCode::Char('k') => self.list.select_previous(),
+				KeyCode::Home | KeyCode::Char('g') => self.list.select_first(),
+				KeyCode::End | KeyCode::Char('G') => self.list.select_last(),
+				KeyCode::Enter | KeyCode::Char('r') => self.set_status(Status::Read)?,
+				KeyCode::Char('s') => self.set_status(Status::Skipped)?,
+				KeyCode::Char('u') => self.set_status(Status::Unread)?,
+				KeyCode::Char('*') | KeyCode::Char('f') => self.toggle_star()?,
+				KeyCode::Char('t') => {
+					self.type_filter = self.type_filter.next();
+					self.refresh(None);
+				},
+				KeyCode::Char('v') => {
+					self.view = self.view.next();
+					self.refresh(None);
+				},
+				KeyCode::Char('+') | KeyCode::Char('=') => {
+					self.min_amount += 5.0;
+					self.refresh(None);
+				},
+				KeyCode::Char('-') => {
+					self.min_amount = (self.min_amount - 5.0).max(0.0);
+					self.refresh(None);
+				},
+				_ => {},
+			}
+		}
+	}
+
+	fn selected(&self) -> Option<usize> {
+		self.list.selected().and_then(|selected| self.visible.get(selected).copied())
+	}
+
+	fn set_status(&mut self, status: Status) -> std::io::Result<()> {
+		let Some(position) = self.selected() else {
+			return Ok(());
+		};
+		self.state.items.entry(self.events[position].id().to_string()).or_default().status = status;
+		self.refresh(Some(position));
+		self.save()
+	}
+
+	fn toggle_star(&mut self) -> std::io::Result<()> {
+		let Some(position) = self.selected() else {
+			return Ok(());
+		};
+		let item = self.state.items.entry(self.events[position].id().to_string()).or_default();
+		item.starred = !item.starred;
+		// starring doesn't move on to the next one
+		self.refresh(None);
+		self.save()
+	}
+
+	fn save(&self) -> std::io::Result<()> {
+		let json = serde_json::to_string_pretty(&self.state).map_err(std::io::Error::other)?;
+		std::fs::write(&self.state_path, json)
+	}
+
+	// recompute what is shown, keeping the selection near the item that was just handled
+	fn refresh(&mut self, handled: Option<usize>) {
+		let previous = self.list.selected().unwrap_or(0);
+		self.visible = (0..self.events.len())
+			.filter(|&position| {
+				let event = &self.events[position];
+				let state = self.item(event);
+				let in_view = match self.view {
+					View::Unread => state.status == Status::Unread,
+					View::Starred => state.starred,
+					View::All => true,
+				};
+				in_view && self.type_filter.matches(event) && self.amount_matches(event)
+			})
+			.collect();
+
+		let selected = match handled.and_then(|handled| self.visible.iter().position(|&position| position == handled)) {
+			// still visible, move on to the next one
+			Some(index) if index + 1 < self.visible.len() => Some(index + 1),
+			Some(index) => Some(index),
+			// it disappeared from the list so whatever took its place is next
+			None if self.visible.is_empty() => None,
+			None => Some(previous.min(self.visible.len() - 1)),
+		};
+		self.list.select(selected);
+	}
+
+	fn item(&self, event: &ExportStructs) -> ItemState {
+		self.state.items.get(event.id()).copied().unwrap_or_default()
+	}
+
+	// memberships don't have an amount and aren't hidden by it
+	fn amount_matches(&self, event: &ExportStructs) -> bool {
+		if self.min_amount <= 0.0 {
+			return true;
+		}
+		let (currency, value, timestamp) = match event {
+			ExportStructs::Donation(e) => (&e.currency, e.amount_value, &e.timestamp),
+			ExportStructs::Sticker(e) => (&e.currency, e.amount_value, &e.timestamp),
+			_ => return true,
+		};
+		let (Some(currency), Some(value)) = (currency, value) else { return false };
+		let date = timestamp.as_deref().and_then(|timestamp| DateTime::parse_from_rfc3339(timestamp).ok()).map(|time| time.date_naive());
+		filter::at_least(&Amount::from_value(currency, value), &Amount::from_value(&self.base_currency, self.min_amount), date, self.rate_table)
+	}
+
+	fn draw(&mut self, frame: &mut Frame) {
+		let [status_area, main_area, help_area] = Layout::vertical([
+			Constraint::Length(1),
+			Constraint::Min(0),
+			Constraint::Length(1),
+		]).areas(frame.area());
+		let [list_area, detail_area] = Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)]).areas(main_area);
+
+		let unread = self.events.iter().filter(|event| self.item(event).status == Status::Unread).count();
+		let status = format!(
+			" {} unread of {} | showing {} {} | minimum amount {:.0} {} | {} shown",
+			unread, self.events.len(), self.view.name(), self.type_filter.name(), self.min_amount, self.base_currency, self.visible.len(),
+		);
+		frame.render_widget(Paragraph::new(status).style(Style::new().add_modifier(Modifier::REVERSED)), status_area);
+
+		let items: Vec<ListItem> = self.visible.iter().map(|&position| {
+			let event = &self.events[position];
+			let state = self.item(event);
+			let marker = match state.status {
+				Status::Unread => "  ",
+				Status::Read => "✓ ",
+				Status::Skipped => "» ",
+			};
+			let star = if state.starred { "★ " } else { "  " };
+			let (red, green, blue) = rgb(body_color(event));
+			ListItem::new(Line::from(vec![
+				Span::raw(marker),
+				Span::styled(star, Style::new().fg(Color::Yellow)),
+				Span::styled("█ ", Style::new().fg(Color::Rgb(red, green, blue))),
+				Span::raw(format!("{} ", event.time())),
+				Span::styled(event.username().to_string(), Style::new().add_modifier(Modifier::BOLD)),
+				Span::raw(format!(" {}", headline(event))),
+			]))
+		}).collect();
+		let list = List::new(items)
+			.block(Block::new().borders(Borders::ALL).title(" queue "))
+			.highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+		frame.render_stateful_widget(list, list_area, &mut self.list);
+
+		let detail = match self.selected() {
+			Some(position) => {
+				let event = &self.events[position];
+				let (red, green, blue) = rgb(header_color(event));
+				let mut lines = vec![
+					Line::from(Span::styled(format!(" {} ", headline(event)), Style::new().fg(Color::Black).bg(Color::Rgb(red, green, blue)))),
+					Line::from(format!("{} (https://youtube.com/channel/{})", event.username(), event.channel_id())),
+					Line::from(format!("time: {}", event.time())),
+					Line::from(""),
+				];
+				lines.extend(body(event).lines().map(|line| Line::from(line.to_string())));
+				Paragraph::new(lines).wrap(Wrap { trim: false })
+			},
+			None => Paragraph::new("nothing left to read"),
+		};
+		frame.render_widget(detail.block(Block::new().borders(Borders::ALL)), detail_area);
+
+		let help = " j/k move  enter read  s skip  u unread  * star  t type  v view  +/- amount  q quit";
+		frame.render_widget(Paragraph::new(help).style(Style::new().fg(Color::DarkGray)), help_area);
+	}
+}
+
+fn headline(event: &ExportStructs) -> String {
+	match event {
+		ExportStructs::Donation(e) => e.amount.clone(),
+		ExportStructs::Sticker(e) => format!("{} sticker", e.sticker_cost),
+		ExportStructs::Membership(e) => e.months.clone(),
+		ExportStructs::GiftingMembership(e) => format!("gifted {} memberships", e.number),
+		ExportStructs::GiftMembership(e) => format!("got a gift membership from {}", e.sender),
+		ExportStructs::TextMessage(_) => String::new(),
+	}
+}
+
+fn body(event: &ExportStructs) -> String {
+	match event {
+		ExportStructs::Donation(e) => e.message.clone().unwrap_or_default(),
+		ExportStructs::Sticker(e) => e.sticker_description.clone(),
+		ExportStructs::Membership(e) => e.message.clone().unwrap_or_default(),
+		ExportStructs::TextMessage(e) => e.message.clone(),
+		_ => String::new(),
+	}
+}
+
+fn header_color(event: &ExportStructs) -> i64 {
+	match event {
+		ExportStructs::Donation(e) => e.header_color,
+		ExportStructs::Sticker(e) => e.header_color,
+		ExportStructs::Membership(e) => e.header_color,
+		ExportStructs::GiftingMembership(e) => e.header_color,
+		ExportStructs::GiftMembership(e) => e.header_color,
+		ExportStructs::TextMessage(_) => 0xffffff,
+	}
+}
+
+fn body_color(event: &ExportStructs) -> i64 {
+	match event {
+		ExportStructs::Donation(e) => e.body_color,
+		ExportStructs::Sticker(e) => e.body_color,
+		ExportStructs::Membership(e) => e.body_color,
+		ExportStructs::GiftingMembership(e) => e.body_color,
+		ExportStructs::GiftMembership(e) => e.body_color,
+		ExportStructs::TextMessage(_) => 0xffffff,
+	}
+}
+
+fn rgb(color: i64) -> (u8, u8, u8) {
+	(((color >> 16) & 0xff) as u8, ((color >> 8) & 0xff) as u8, (color & 0xff) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use superchat_extractor::export;
+
+	fn queue(rate_table: Option<&RateTable>) -> Queue<'_> {
+		let lines = [
+			r#"{"type":"Donation","id":"usd","username":"a","channel_id":"UCa","amount":"$5.00","message":null,"timestamp":"2023-05-13T17:46:45Z","header_color":0,"body_color":0,"thumbnail_url":""}"#,
+			r#"{"type":"Donation","id":"yen","username":"b","channel_id":"UCb","amount":"¥1,000","message":null,"timestamp":"2023-05-13T17:46:45Z","header_color":0,"body_color":0,"thumbnail_url":""}"#,
+			r#"{"type":"Membership","id":"member","username":"c","channel_id":"UCc","months":"New member.","message":null,"header_color":0,"body_color":0,"thumbnail_url":""}"#,
+		];
+		Queue {
+			events: lines.iter().map(|line| export::parse_line(line).unwrap()).collect(),
+			state: SavedState::default(),
+			state_path: String::new(),
+			type_filter: TypeFilter::All,
+			view: View::Unread,
+			min_amount: 0.0,
+			base_currency: "USD".to_string(),
+			rate_table,
+			visible: Vec::new(),
+			list: ListState::default(),
+		}
+	}
+
+	fn shown(queue: &Queue) -> Vec<String> {
+		queue.visible.iter().map(|&position| queue.events[position].id().to_string()).collect()
+	}
+
+	#[test]
+	fn minimum_amount_compares_currencies_with_rates_and_keeps_memberships() {
+		let mut without_rates = queue(None);
+		without_rates.min_amount = 5.0;
+		without_rates.refresh(None);
+		assert_eq!(shown(&without_rates), ["usd", "member"]);
+
+		let path = std::env::temp_dir().join(format!("superchat-extractor-queue-{}.csv", std::process::id()));
+		std::fs::write(&path, "JPY,0.0072\n").unwrap();
+		let rate_table = RateTable::load(path.to_str().unwrap(), "USD").unwrap();
+		std::fs::remove_file(&path).unwrap();
+		let mut with_rates = queue(Some(&rate_table));
+		with_rates.min_amount = 7.0;
+		with_rates.refresh(None);
+		assert_eq!(shown(&with_rates), ["yen", "member"]);
+	}
+
+	#[test]
+	fn state_follows_the_event_id() {
+		let mut first = queue(None);
+		first.refresh(None);
+		first.list.select(Some(1));
+		first.state_path = std::env::temp_dir().join(format!("superchat-extractor-queue-{}.queue.json", std::process::id())).to_str().unwrap().to_string();
+		first.set_status(Status::Read).unwrap();
+
+		// processed again with a filter that drops the first superchat
+		let mut again = queue(None);
+		again.events.remove(0);
+		again.state = serde_json::from_str(&std::fs::read_to_string(&first.state_path).unwrap()).unwrap();
+		std::fs::remove_file(&first.state_path).unwrap();
+		again.refresh(None);
+		assert_eq!(shown(&again), ["member"]);
+	}
+}
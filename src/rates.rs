@@ -0,0 +1,155 @@
+// offline currency conversion using a rate table from a local file
+//
+// csv files have one rate per line:
+//   currency,rate[,date]
+//   JPY,0.0067
+//   EUR,1.08,2023-05-13
+// json files are an array of the same fields:
+//   [{"currency": "JPY", "rate": 0.0067}, {"currency": "EUR", "rate": 1.08, "date": "2023-05-13"}]
+//
+// rate is how much one unit of the currency is worth in the base currency
+
+use superchat_extractor::currency::Amount;
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Deserialize, Debug)]
+struct RateEntry {
+	currency: String,
+	rate: f64,
+	date: Option<NaiveDate>,
+}
+
+pub struct RateTable {
+	pub base: String,
+	rates: HashMap<String, Vec<(Option<NaiveDate>, f64)>>,
+}
+
+impl RateTable {
+	pub fn load(path: &str, base: &str) -> Result<RateTable, String> {
+		let contents = std::fs::read_to_string(path).map_err(|e| format!("could not read {path}: {e}"))?;
+		let entries = if path.to_lowercase().ends_with(".json") {
+			serde_json::from_str::<Vec<RateEntry>>(&contents).map_err(|e| format!("could not parse {path}: {e}"))?
+		} else {
+			parse_csv(&contents).map_err(|e| format!("could not parse {path}: {e}"))?
+		};
+
+		let mut rates: HashMap<String, Vec<(Option<NaiveDate>, f64)>> = HashMap::new();
+		for entry in entries {
+			rates.entry(entry.currency.to_uppercase()).or_default().push((entry.date, entry.rate));
+		}
+		for currency_rates in rates.values_mut() {
+			currency_rates.sort_by_key(|(date, _)| *date);
+		}
+
+		Ok(RateTable { base: base.to_uppercase(), rates })
+	}
+
+	// picks the rate for the day of the event, the latest one before it,
+	// an undated one, or the closest one after it in that order
+	// the result is rounded to the smallest unit of the base currency
+	pub fn convert(&self, amount: &Amount, date: Option<NaiveDate>) -> Option<Amount> {
+		if amount.currency == self.base {
+			return Some(amount.clone());
+		}
+		let currency_rates = self.rates.get(&amount.currency)?;
+		let undated = currency_rates.iter().find(|(rate_date, _)| rate_date.is_none());
+		let earlier = date.and_then(|date| {
+			currency_rates.iter().rev().find(|(rate_date, _)| rate_date.is_some_and(|rate_date| rate_date <= date))
+		});
+		let later = currency_rates.iter().find(|(rate_date, _)| rate_date.is_some());
+		let (_, rate) = earlier.or(undated).or(later)?;
+		Some(Amount::from_value(&self.base, amount.value() * rate))
+	}
+}
+
+// the utc day of a timestampUsec value
+pub fn date_from_usec(usec: i64) -> Option<NaiveDate> {
+	chrono::DateTime::from_timestamp(usec / 1_000_000, 0).map(|datetime| datetime.date_naive())
+}
+
+fn parse_csv(contents: &str) -> Result<Vec<RateEntry>, String> {
+	let mut reader = csv::ReaderBuilder::new()
+		.has_headers(false)
+		.flexible(true)
+		.comment(Some(b'#'))
+		.trim(csv::Trim::All)
+		.from_reader(contents.as_bytes());
+	let mut entries = Vec::new();
+	for (index, record) in reader.records().enumerate() {
+		let record = record.map_err(|e| e.to_string())?;
+		let line = record.position().map(|position| position.line()).unwrap_or(0);
+		let fields: Vec<&str> = record.iter().collect();
+		// skip the header if there is one, comments can come before it
+		if index == 0 && fields[0].eq_ignore_ascii_case("currency") {
+			continue;
+		}
+		let (currency, rate, date) = match fields.as_slice() {
+			[""] => continue,
+			[currency, rate] => (currency, rate, None),
+			[currency, rate, date] => (currency, rate, Some(date)),
+			_ => return Err(format!("line {line}: expected currency,rate[,date]")),
+		};
+		let rate = rate.parse::<f64>().map_err(|e| format!("line {line}: bad rate {rate}: {e}"))?;
+		let date = match date {
+			Some(date) if !date.is_empty() => Some(
+				NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| format!("line {line}: bad date {date}: {e}"))?,
+			),
+			_ => None,
+		};
+		entries.push(RateEntry { currency: currency.to_string(), rate, date });
+	}
+	Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn table(csv: &str) -> RateTable {
+		let path = std::env::temp_dir().join(format!("superchat-extractor-rates-{}-{}.csv", std::process::id(), csv.len()));
+		std::fs::write(&path, csv).unwrap();
+		let table = RateTable::load(path.to_str().unwrap(), "usd");
+		std::fs::remove_file(&path).unwrap();
+		table.unwrap()
+	}
+
+	fn yen(minor_units: i64) -> Amount {
+		Amount { currency: "JPY".to_string(), minor_units }
+	}
+
+	fn date(text: &str) -> Option<NaiveDate> {
+		NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()
+	}
+
+	#[test]
+	fn picks_the_rate_for_the_day() {
+		let rates = table("# rates from the bank\nCurrency, Rate, Date\nJPY,0.0070,2023-05-10\nJPY,0.0080,2023-05-20\n\nJPY,0.0050\n");
+		let convert = |day| rates.convert(&yen(1000), date(day)).map(|amount| amount.minor_units);
+		// the latest rate on or before the day
+		assert_eq!(convert("2023-05-10"), Some(700));
+		assert_eq!(convert("2023-05-15"), Some(700));
+		assert_eq!(convert("2023-06-01"), Some(800));
+		// before every dated rate the undated one is used
+		assert_eq!(convert("2023-01-01"), Some(500));
+		assert_eq!(rates.convert(&yen(1000), None).map(|amount| amount.minor_units), Some(500));
+	}
+
+	#[test]
+	fn falls_back_to_the_closest_later_rate() {
+		let rates = table("EUR,1.10,2023-05-20\nEUR,1.05,2023-05-30\n");
+		let euros = Amount { currency: "EUR".to_string(), minor_units: 1000 };
+		assert_eq!(rates.convert(&euros, date("2023-05-01")).map(|amount| amount.minor_units), Some(1100));
+		// the base currency needs no rate and unknown currencies can't be converted
+		assert_eq!(rates.convert(&Amount { currency: "USD".to_string(), minor_units: 123 }, None).map(|amount| amount.minor_units), Some(123));
+		assert_eq!(rates.convert(&yen(1000), None), None);
+	}
+
+	#[test]
+	fn reports_bad_lines() {
+		assert!(parse_csv("JPY,0.007\nJPY,lots\n").unwrap_err().starts_with("line 2: bad rate lots"));
+		assert!(parse_csv("JPY,0.007,2023-05-10,extra\n").unwrap_err().starts_with("line 1:"));
+		assert!(parse_csv("\"JPY, quoted\",0.007\n").is_ok());
+	}
+}
@@ -0,0 +1,305 @@
+// the other end of --live
+// extractors connect and speak the protocol in protocol.rs, one connection per chat file
+
+use crate::protocol::{self, DecodeError, Message};
+use superchat_extractor::currency::Amount;
+use superchat_extractor::export::ExportStructs;
+use interprocess::local_socket::{LocalSocketListener, LocalSocketStream};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+pub const SOCKET_NAME: &str = "@live_donations";
+
+#[derive(Clone)]
+pub struct Options {
+	pub socket: String,
+	// append every stream's events to <dir>/<stream>.jsonl
+	pub output_dir: Option<PathBuf>,
+}
+
+// what the server knows about one stream, across every client that sent events for it
+#[derive(Default, Debug)]
+struct StreamView {
+	connected_clients: usize,
+	events: usize,
+	superchats: usize,
+	stickers: usize,
+	memberships: usize,
+	gifts: usize,
+	redemptions: usize,
+	messages: usize,
+	// spend per currency code, in minor units
+	totals: HashMap<String, i64>,
+	// <dir>/<stream>.jsonl, shared by every client of the stream so their lines don't get mixed up
+	output: Option<File>,
+	// seq of the next event, kept across reconnects since the client only resends what it couldn't deliver
+	next_seq: u64,
+	// events that never arrived, seen as jumps in seq
+	missed: u64,
+}
+
+type Streams = Arc<Mutex<HashMap<String, StreamView>>>;
+
+// what gets written to stdout for every event so other programs can pick them up
+#[derive(Serialize)]
+struct Forwarded<'a> {
+	stream: &'a str,
+	event: &'a ExportStructs,
+}
+
+pub fn run(options: Options) -> std::io::Result<()> {
+	if let Some(dir) = &options.output_dir {
+		std::fs::create_dir_all(dir)?;
+	}
+	let listener = LocalSocketListener::bind(options.socket.as_str())?;
+	eprintln!("listening on {}", options.socket);
+
+	let streams: Streams = Arc::default();
+	for client in listener.incoming() {
+		let client = match client {
+			Ok(client) => client,
+			Err(e) => {
+				eprintln!("failed to accept client: {e}");
+				continue;
+			}
+		};
+		let streams = streams.clone();
+		let options = options.clone();
+		std::thread::spawn(move || {
+			if let Err(e) = handle_client(client, &streams, &options) {
+				eprintln!("client failed: {e}");
+			}
+		});
+	}
+	Ok(())
+}
+
+fn handle_client(client: LocalSocketStream, streams: &Streams, options: &Options) -> std::io::Result<()> {
+	let mut lines = BufReader::new(client).lines();
+	let Some(first) = lines.next().transpose()? else {
+		// connected and left without saying anything
+		return Ok(());
+	};
+	match protocol::decode(&first) {
+		Ok(Message::Hello { client }) => eprintln!("{client} connected"),
+		Ok(message) => {
+			eprintln!("client didn't start with hello, got {message:?}");
+			return Ok(());
+		},
+		Err(e) => {
+			eprintln!("client didn't start with hello: {e}");
+			return Ok(());
+		},
+	}
+
+	// streams this connection announced and hasn't ended yet
+	let mut started: HashSet<String> = HashSet::new();
+	let result = read_messages(&mut lines, &mut started, streams, options);
+
+	// whatever is left went away without stream_end
+	for stream in started {
+		stream_ended(&stream, streams, "client disconnected");
+	}
+	result
+}
+
+fn read_messages(lines: &mut impl Iterator<Item = std::io::Result<String>>, started: &mut HashSet<String>, streams: &Streams, options: &Options) -> std::io::Result<()> {
+	for line in lines {
+		let line = line?;
+		if line.is_empty() {
+			continue;
+		}
+		let message = match protocol::decode(&line) {
+			Ok(message) => message,
+			Err(e @ DecodeError::UnsupportedVersion(_)) => {
+				eprintln!("dropping client: {e}");
+				return Ok(());
+			},
+			Err(e) => {
+				eprintln!("ignoring line: {e}");
+				continue;
+			},
+		};
+
+		match message {
+			Message::Hello { client } => eprintln!("{client} said hello again"),
+			Message::StreamStart { stream, metadata } => {
+				if started.contains(&stream) {
+					continue;
+				}
+				match (&metadata.title, &metadata.channel) {
+					(Some(title), Some(channel)) => eprintln!("{stream}: client connected, \"{title}\" by {channel}"),
+					(Some(title), None) => eprintln!("{stream}: client connected, \"{title}\""),
+					_ => eprintln!("{stream}: client connected"),
+				}
+				{
+					let mut streams = streams.lock().expect("stream list poisoned");
+					let view = streams.entry(stream.clone()).or_default();
+					if let (Some(dir), None) = (&options.output_dir, &view.output) {
+						view.output = Some(OpenOptions::new().create(true).append(true).open(dir.join(file_name(&stream)))?);
+					}
+					view.connected_clients += 1;
+				}
+				started.insert(stream);
+			},
+			Message::Event { stream, seq, event } => {
+				if !started.contains(&stream) {
+					eprintln!("{stream}: ignoring event for a stream that wasn't started");
+					continue;
+				}
+				let mut streams = streams.lock().expect("stream list poisoned");
+				let view = streams.get_mut(&stream).expect("stream disappeared");
+				if seq > view.next_seq {
+					eprintln!("{stream}: missed {} events", seq - view.next_seq);
+					view.missed += seq - view.next_seq;
+				}
+				// a second extractor of the same stream counts from 0 again, that isn't a gap
+				view.next_seq = view.next_seq.max(seq + 1);
+				record(view, &event);
+				if let Some(output) = view.output.as_mut() {
+					// the whole line in one write
+					let line = serde_json::to_string(&event).expect("could not serialize event") + "\n";
+					output.write_all(line.as_bytes())?;
+				}
+				drop(streams);
+				let forwarded = serde_json::to_string(&Forwarded { stream: &stream, event: &event }).expect("could not serialize event");
+				println!("{forwarded}");
+			},
+			Message::Heartbeat => {},
+			Message::StreamEnd { stream, events } => {
+				if started.remove(&stream) {
+					// the last ones never came
+					{
+						let mut streams = streams.lock().expect("stream list poisoned");
+						let view = streams.get_mut(&stream).expect("stream disappeared");
+						if events > view.next_seq {
+							eprintln!("{stream}: missed {} events", events - view.next_seq);
+							view.missed += events - view.next_seq;
+							view.next_seq = events;
+						}
+					}
+					stream_ended(&stream, streams, &format!("stream ended after {events} events"));
+				}
+			},
+		}
+	}
+	Ok(())
+}
+
+fn stream_ended(stream: &str, streams: &Streams, reason: &str) {
+	let mut streams = streams.lock().expect("stream list poisoned");
+	let view = streams.get_mut(stream).expect("stream disappeared");
+	view.connected_clients -= 1;
+	if view.connected_clients == 0 {
+		view.output = None;
+	}
+	eprintln!("{stream}: {reason}, {}", summary(view));
+}
+
+fn record(view: &mut StreamView, event: &ExportStructs) {
+	view.events += 1;
+	match event {
+		ExportStructs::Donation(e) => {
+			view.superchats += 1;
+			if let (Some(currency), Some(value)) = (&e.currency, e.amount_value) {
+				*view.totals.entry(currency.clone()).or_insert(0) += Amount::from_value(currency, value).minor_units;
+			}
+		},
+		ExportStructs::Sticker(e) => {
+			view.stickers += 1;
+			if let (Some(currency), Some(value)) = (&e.currency, e.amount_value) {
+				*view.totals.entry(currency.clone()).or_insert(0) += Amount::from_value(currency, value).minor_units;
+			}
+		},
+		ExportStructs::Membership(_) => view.memberships += 1,
+		ExportStructs::GiftingMembership(_) => view.gifts += 1,
+		ExportStructs::GiftMembership(_) => view.redemptions += 1,
+		ExportStructs::TextMessage(_) => view.messages += 1,
+	}
+}
+
+fn summary(view: &StreamView) -> String {
+	let mut totals: Vec<String> = view.totals.iter().map(|(currency, minor_units)| Amount { currency: currency.clone(), minor_units: *minor_units }.to_string()).collect();
+	totals.sort();
+	format!(
+		"{} events so far: {} superchats, {} stickers, {} memberships, {} gifts, {} redemptions, {} messages, spent [{}], {} missed, {} clients still connected",
+		view.events, view.superchats, view.stickers, view.memberships, view.gifts, view.redemptions, view.messages, totals.join(", "), view.missed, view.connected_clients,
+	)
+}
+
+// stream names come from file names but keep them from escaping the output directory
+fn file_name(stream: &str) -> String {
+	let name: String = stream.chars().map(|c| if matches!(c, '/' | '\\' | '\0') { '_' } else { c }).collect();
+	format!("{}.jsonl", name.trim_start_matches('.'))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::live::LiveClient;
+	use crate::metadata::StreamMetadata;
+
+	const DONATION: &str = r#"{"type":"Donation","id":"a","username":"someone","channel_id":"UCaaa","amount":"$5.00","currency":"USD","amount_value":5.0,"message":null,"header_color":0,"body_color":0,"thumbnail_url":""}"#;
+
+	fn event() -> ExportStructs {
+		serde_json::from_str(DONATION).unwrap()
+	}
+
+	fn temp_dir(name: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(format!("superchat-extractor-server-{}-{name}", std::process::id()));
+		let _ = std::fs::remove_dir_all(&dir);
+		std::fs::create_dir_all(&dir).unwrap();
+		dir
+	}
+
+	#[test]
+	fn receives_a_stream_from_the_live_client() {
+		let socket = format!("@superchat-extractor-test-{}", std::process::id());
+		let dir = temp_dir("live");
+		let options = Options { socket: socket.clone(), output_dir: Some(dir.clone()) };
+		let listener = LocalSocketListener::bind(socket.as_str()).unwrap();
+		let streams: Streams = Arc::default();
+		let server = {
+			let streams = streams.clone();
+			std::thread::spawn(move || handle_client(listener.accept().unwrap(), &streams, &options))
+		};
+
+		// hello and stream_start go out on connect, stream_end in finish
+		let mut client = LiveClient::new(&socket, "Title [id]".to_string(), StreamMetadata::default());
+		client.send(event());
+		client.send(event());
+		client.finish();
+		server.join().unwrap().unwrap();
+
+		let streams = streams.lock().unwrap();
+		let view = &streams["Title [id]"];
+		assert_eq!((view.events, view.superchats, view.next_seq, view.missed, view.connected_clients), (2, 2, 2, 0, 0));
+		assert_eq!(view.totals["USD"], 1000);
+		let written = std::fs::read_to_string(dir.join("Title [id].jsonl")).unwrap();
+		assert_eq!(written.lines().count(), 2);
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn counts_gaps_from_the_first_event_and_across_connections() {
+		let options = Options { socket: String::new(), output_dir: None };
+		let streams: Streams = Arc::default();
+		let start = protocol::encode(&Message::StreamStart { stream: "s".to_string(), metadata: StreamMetadata::default() });
+		let event = |seq| protocol::encode(&Message::Event { stream: "s".to_string(), seq, event: event() });
+
+		// the first connection loses seq 0 and 1, then drops
+		let mut started = HashSet::new();
+		read_messages(&mut [start.clone(), event(2), event(3)].into_iter().map(Ok), &mut started, &streams, &options).unwrap();
+		// the reconnect picks up where it left off, loses 5 and the last one
+		let mut started = HashSet::new();
+		let end = protocol::encode(&Message::StreamEnd { stream: "s".to_string(), events: 8 });
+		read_messages(&mut [start, event(4), event(6), end].into_iter().map(Ok), &mut started, &streams, &options).unwrap();
+
+		let streams = streams.lock().unwrap();
+		assert_eq!((streams["s"].events, streams["s"].missed, streams["s"].next_seq), (4, 4, 8));
+	}
+}
@@ -0,0 +1,360 @@
+// archive of extracted events across many streams in one sqlite database
+// events are keyed on their youtube id so processing a file again doesn't duplicate anything
+// writes are committed every COMMIT_EVERY events and whenever a followed file goes quiet,
+// so stopping in the middle of a long stream only loses the last few events
+
+use crate::metadata::StreamMetadata;
+use superchat_extractor::export::ExportStructs;
+use rusqlite::{params, Connection};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS streams (
+	id INTEGER PRIMARY KEY,
+	name TEXT NOT NULL UNIQUE,
+	file TEXT NOT NULL,
+	video_id TEXT,
+	title TEXT,
+	channel TEXT,
+	channel_id TEXT,
+	release_timestamp INTEGER
+);
+CREATE TABLE IF NOT EXISTS authors (
+	channel_id TEXT PRIMARY KEY,
+	username TEXT NOT NULL,
+	thumbnail_url TEXT
+);
+CREATE TABLE IF NOT EXISTS donations (
+	id TEXT PRIMARY KEY,
+	stream_id INTEGER NOT NULL REFERENCES streams(id),
+	channel_id TEXT NOT NULL REFERENCES authors(channel_id),
+	time TEXT NOT NULL,
+	amount TEXT NOT NULL,
+	currency TEXT,
+	amount_value REAL,
+	converted_value REAL,
+	base_currency TEXT,
+	message TEXT,
+	header_color INTEGER NOT NULL,
+	body_color INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS memberships (
+	id TEXT PRIMARY KEY,
+	stream_id INTEGER NOT NULL REFERENCES streams(id),
+	channel_id TEXT NOT NULL REFERENCES authors(channel_id),
+	time TEXT NOT NULL,
+	months TEXT NOT NULL,
+	message TEXT
+);
+CREATE TABLE IF NOT EXISTS gifts (
+	id TEXT PRIMARY KEY,
+	stream_id INTEGER NOT NULL REFERENCES streams(id),
+	channel_id TEXT NOT NULL REFERENCES authors(channel_id),
+	time TEXT NOT NULL,
+	number INTEGER
+);
+CREATE TABLE IF NOT EXISTS redemptions (
+	id TEXT PRIMARY KEY,
+	stream_id INTEGER NOT NULL REFERENCES streams(id),
+	channel_id TEXT NOT NULL REFERENCES authors(channel_id),
+	time TEXT NOT NULL,
+	sender TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS stickers (
+	id TEXT PRIMARY KEY,
+	stream_id INTEGER NOT NULL REFERENCES streams(id),
+	channel_id TEXT NOT NULL REFERENCES authors(channel_id),
+	time TEXT NOT NULL,
+	amount TEXT NOT NULL,
+	currency TEXT,
+	amount_value REAL,
+	converted_value REAL,
+	base_currency TEXT,
+	description TEXT NOT NULL,
+	image_url TEXT NOT NULL,
+	color INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS messages (
+	id TEXT PRIMARY KEY,
+	stream_id INTEGER NOT NULL REFERENCES streams(id),
+	channel_id TEXT NOT NULL REFERENCES authors(channel_id),
+	time TEXT NOT NULL,
+	message TEXT NOT NULL,
+	badges TEXT NOT NULL
+);
+-- kind is 'message' for a single deleted message and 'author' when all of a channel's messages were removed
+CREATE TABLE IF NOT EXISTS removals (
+	stream_id INTEGER NOT NULL REFERENCES streams(id),
+	kind TEXT NOT NULL,
+	target TEXT NOT NULL,
+	PRIMARY KEY (stream_id, kind, target)
+);
+-- polls get updated while people vote, only the latest state is kept
+CREATE TABLE IF NOT EXISTS polls (
+	id TEXT PRIMARY KEY,
+	stream_id INTEGER NOT NULL REFERENCES streams(id),
+	question TEXT NOT NULL,
+	metadata TEXT NOT NULL,
+	choices TEXT NOT NULL
+);
+";
+
+// columns added to streams after the first release, databases made before that don't have them
+const STREAM_METADATA_COLUMNS: [(&str, &str); 5] = [
+	("video_id", "TEXT"),
+	("title", "TEXT"),
+	("channel", "TEXT"),
+	("channel_id", "TEXT"),
+	("release_timestamp", "INTEGER"),
+];
+
+// tables with one row per event, removals mark rows in all of them
+// the removed columns were added later so they are added to older databases in open
+const EVENT_TABLES: [&str; 6] = ["donations", "memberships", "gifts", "redemptions", "stickers", "messages"];
+
+const COMMIT_EVERY: usize = 500;
+
+pub struct Archive {
+	conn: Connection,
+	stream_id: i64,
+	// writes since the last commit
+	uncommitted: usize,
+}
+
+impl Archive {
+	pub fn open(path: &str, stream_name: &str, file: &str, metadata: &StreamMetadata) -> rusqlite::Result<Archive> {
+		let conn = Connection::open(path)?;
+		conn.execute_batch(SCHEMA)?;
+		add_missing_columns(&conn)?;
+		// metadata that couldn't be found this time doesn't overwrite what an earlier run found
+		conn.execute(
+			"INSERT INTO streams (name, file, video_id, title, channel, channel_id, release_timestamp) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+			ON CONFLICT(name) DO UPDATE SET file = excluded.file,
+				video_id = coalesce(excluded.video_id, video_id),
+				title = coalesce(excluded.title, title),
+				channel = coalesce(excluded.channel, channel),
+				channel_id = coalesce(excluded.channel_id, channel_id),
+				release_timestamp = coalesce(excluded.release_timestamp, release_timestamp)",
+			params![stream_name, file, metadata.video_id, metadata.title, metadata.channel, metadata.channel_id, metadata.release_timestamp],
+		)?;
+		let stream_id = conn.query_row("SELECT id FROM streams WHERE name = ?1", params![stream_name], |row| row.get(0))?;
+		conn.execute_batch("BEGIN")?;
+		Ok(Archive { conn, stream_id, uncommitted: 0 })
+	}
+
+	pub fn insert_event(&mut self, id: &str, event: &ExportStructs) -> rusqlite::Result<()> {
+		match event {
+			ExportStructs::Donation(e) => {
+				self.upsert_author(&e.channel_id, &e.username, &e.thumbnail_url)?;
+				self.conn.execute(
+					"INSERT OR REPLACE INTO donations (id, stream_id, channel_id, time, amount, currency, amount_value, converted_value, base_currency, message, header_color, body_color)
+					VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+					params![id, self.stream_id, e.channel_id, e.time, e.amount, e.currency, e.amount_value, e.converted_value, e.base_currency, e.message, e.header_color, e.body_color],
+				)?;
+			},
+			ExportStructs::Membership(e) => {
+				self.upsert_author(&e.channel_id, &e.username, &e.thumbnail_url)?;
+				self.conn.execute(
+					"INSERT OR REPLACE INTO memberships (id, stream_id, channel_id, time, months, message) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+					params![id, self.stream_id, e.channel_id, e.time, e.months, e.message],
+				)?;
+			},
+			ExportStructs::GiftingMembership(e) => {
+				self.upsert_author(&e.channel_id, &e.username, &e.thumbnail_url)?;
+				self.conn.execute(
+					"INSERT OR REPLACE INTO gifts (id, stream_id, channel_id, time, number) VALUES (?1, ?2, ?3, ?4, ?5)",
+					params![id, self.stream_id, e.channel_id, e.time, e.number.parse::<i64>().ok()],
+				)?;
+			},
+			ExportStructs::GiftMembership(e) => {
+				self.upsert_author(&e.channel_id, &e.username, &e.thumbnail_url)?;
+				self.conn.execute(
+					"INSERT OR REPLACE INTO redemptions (id, stream_id, channel_id, time, sender) VALUES (?1, ?2, ?3, ?4, ?5)",
+					params![id, self.stream_id, e.channel_id, e.time, e.sender],
+				)?;
+			},
+			ExportStructs::Sticker(e) => {
+				self.upsert_author(&e.channel_id, &e.username, &e.thumbnail_url)?;
+				self.conn.execute(
+					"INSERT OR REPLACE INTO stickers (id, stream_id, channel_id, time, amount, currency, amount_value, converted_value, base_currency, description, image_url, color)
+					VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+					params![id, self.stream_id, e.channel_id, e.time, e.sticker_cost, e.currency, e.amount_value, e.converted_value, e.base_currency, e.sticker_description, e.sticker_image_url, e.body_color],
+				)?;
+			},
+			ExportStructs::TextMessage(e) => {
+				self.upsert_author(&e.channel_id, &e.username, "")?;
+				self.conn.execute(
+					"INSERT OR REPLACE INTO messages (id, stream_id, channel_id, time, message, badges) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+					params![id, self.stream_id, e.channel_id, e.time, e.message, serde_json::to_string(&e.badges).expect("could not serialize badges")],
+				)?;
+			},
+		}
+		self.written()
+	}
+
+	// a removal that passed the filters, what it removed gets marked too
+	pub fn insert_removal(&mut self, kind: &str, target: &str) -> rusqlite::Result<()> {
+		self.conn.execute(
+			"INSERT OR IGNORE INTO removals (stream_id, kind, target) VALUES (?1, ?2, ?3)",
+			params![self.stream_id, kind, target],
+		)?;
+		self.mark_removed(kind, target)
+	}
+
+	// marks what was already archived as removed, a message by its id or everything an author sent so far
+	// also done for removals the filters leave out, the same way the export still marks its events
+	pub fn mark_removed(&mut self, kind: &str, target: &str) -> rusqlite::Result<()> {
+		let (filter, reason) = match kind {
+			"author" => ("stream_id = ?1 AND channel_id = ?2", "author_wiped"),
+			_ => ("stream_id = ?1 AND id = ?2", "deleted"),
+		};
+		for table in EVENT_TABLES {
+			// the first reason sticks, like in the export
+			self.conn.execute(
+				&format!("UPDATE {table} SET removed = 1, removed_reason = ?3 WHERE {filter} AND removed = 0"),
+				params![self.stream_id, target, reason],
+			)?;
+		}
+		self.written()
+	}
+
+	// choices are stored as a json array of [choice, vote percentage]
+	pub fn insert_poll(&mut self, id: &str, question: &str, metadata: &str, choices: &[(String, String)]) -> rusqlite::Result<()> {
+		let choices = serde_json::to_string(choices).expect("could not serialize poll choices");
+		self.conn.execute(
+			"INSERT OR REPLACE INTO polls (id, stream_id, question, metadata, choices) VALUES (?1, ?2, ?3, ?4, ?5)",
+			params![id, self.stream_id, question, metadata, choices],
+		)?;
+		self.written()
+	}
+
+	// commits what was written so far, called when a followed file has nothing new
+	pub fn checkpoint(&mut self) -> rusqlite::Result<()> {
+		if self.uncommitted > 0 {
+			self.conn.execute_batch("COMMIT; BEGIN")?;
+			self.uncommitted = 0;
+		}
+		Ok(())
+	}
+
+	pub fn finish(self) -> rusqlite::Result<()> {
+		self.conn.execute_batch("COMMIT")
+	}
+
+	fn written(&mut self) -> rusqlite::Result<()> {
+		self.uncommitted += 1;
+		if self.uncommitted >= COMMIT_EVERY {
+			self.checkpoint()?;
+		}
+		Ok(())
+	}
+
+	// keeps the most recent name and avatar, chat messages don't come with an avatar
+	fn upsert_author(&mut self, channel_id: &str, username: &str, thumbnail_url: &str) -> rusqlite::Result<()> {
+		self.conn.execute(
+			"INSERT INTO authors (channel_id, username, thumbnail_url) VALUES (?1, ?2, nullif(?3, ''))
+			ON CONFLICT(channel_id) DO UPDATE SET username = excluded.username, thumbnail_url = coalesce(excluded.thumbnail_url, thumbnail_url)",
+			params![channel_id, username, thumbnail_url],
+		)?;
+		Ok(())
+	}
+}
+
+fn add_missing_columns(conn: &Connection) -> rusqlite::Result<()> {
+	for (column, kind) in STREAM_METADATA_COLUMNS {
+		add_column(conn, "streams", column, kind)?;
+	}
+	for table in EVENT_TABLES {
+		add_column(conn, table, "removed", "INTEGER NOT NULL DEFAULT 0")?;
+		add_column(conn, table, "removed_reason", "TEXT")?;
+	}
+	Ok(())
+}
+
+fn add_column(conn: &Connection, table: &str, column: &str, kind: &str) -> rusqlite::Result<()> {
+	let mut statement = conn.prepare(&format!("SELECT name FROM pragma_table_info('{table}')"))?;
+	let existing = statement.query_map([], |row| row.get::<_, String>(0))?.collect::<rusqlite::Result<Vec<String>>>()?;
+	if !existing.iter().any(|name| name == column) {
+		conn.execute_batch(&format!("ALTER TABLE {table} ADD COLUMN {column} {kind}"))?;
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use superchat_extractor::export;
+
+	fn temp_database(name: &str) -> String {
+		let path = std::env::temp_dir().join(format!("superchat-extractor-sqlite-{}-{name}.db", std::process::id()));
+		let _ = std::fs::remove_file(&path);
+		path.to_str().unwrap().to_string()
+	}
+
+	fn message(id: &str, channel_id: &str) -> ExportStructs {
+		export::parse_line(&format!(
+			r#"{{"type":"TextMessage","id":"{id}","username":"someone","channel_id":"{channel_id}","time":"0:01","badges":["Moderator"],"message":"hi"}}"#
+		)).unwrap()
+	}
+
+	fn removed(conn: &Connection, id: &str) -> (bool, Option<String>) {
+		conn.query_row("SELECT removed, removed_reason FROM messages WHERE id = ?1", params![id], |row| Ok((row.get(0)?, row.get(1)?))).unwrap()
+	}
+
+	#[test]
+	fn archives_chat_and_marks_removed_rows() {
+		let path = temp_database("removals");
+		let mut archive = Archive::open(&path, "stream", "stream.live_chat.json", &StreamMetadata::default()).unwrap();
+		for (id, channel_id) in [("a", "UCa"), ("b", "UCa"), ("c", "UCc")] {
+			archive.insert_event(id, &message(id, channel_id)).unwrap();
+		}
+		archive.insert_removal("message", "c").unwrap();
+		archive.insert_removal("author", "UCa").unwrap();
+		// sent after the wipe, so still there
+		archive.insert_event("d", &message("d", "UCa")).unwrap();
+		archive.insert_removal("author", "UCc").unwrap();
+		archive.finish().unwrap();
+
+		let conn = Connection::open(&path).unwrap();
+		assert_eq!(removed(&conn, "a"), (true, Some("author_wiped".to_string())));
+		assert_eq!(removed(&conn, "b"), (true, Some("author_wiped".to_string())));
+		assert_eq!(removed(&conn, "c"), (true, Some("deleted".to_string())));
+		assert_eq!(removed(&conn, "d"), (false, None));
+		let badges: String = conn.query_row("SELECT badges FROM messages WHERE id = 'a'", [], |row| row.get(0)).unwrap();
+		assert_eq!(badges, r#"["Moderator"]"#);
+		std::fs::remove_file(&path).unwrap();
+	}
+
+	#[test]
+	fn filtered_out_removals_only_mark_rows() {
+		let path = temp_database("filtered");
+		let mut archive = Archive::open(&path, "stream", "stream.live_chat.json", &StreamMetadata::default()).unwrap();
+		archive.insert_event("a", &message("a", "UCa")).unwrap();
+		archive.mark_removed("message", "a").unwrap();
+		archive.finish().unwrap();
+
+		let conn = Connection::open(&path).unwrap();
+		assert_eq!(removed(&conn, "a"), (true, Some("deleted".to_string())));
+		let removals: usize = conn.query_row("SELECT count(*) FROM removals", [], |row| row.get(0)).unwrap();
+		assert_eq!(removals, 0);
+		std::fs::remove_file(&path).unwrap();
+	}
+
+	#[test]
+	fn commits_without_finish() {
+		let path = temp_database("batches");
+		let mut archive = Archive::open(&path, "stream", "stream.live_chat.json", &StreamMetadata::default()).unwrap();
+		for number in 0..COMMIT_EVERY + 1 {
+			let id = number.to_string();
+			archive.insert_event(&id, &message(&id, "UCa")).unwrap();
+		}
+		let count = |conn: &Connection| conn.query_row("SELECT count(*) FROM messages", [], |row| row.get::<_, usize>(0)).unwrap();
+		let reader = Connection::open(&path).unwrap();
+		assert_eq!(count(&reader), COMMIT_EVERY);
+		archive.checkpoint().unwrap();
+		assert_eq!(count(&reader), COMMIT_EVERY + 1);
+		// dropped like on ctrl-c, everything up to the checkpoint stays
+		drop(archive);
+		assert_eq!(count(&reader), COMMIT_EVERY + 1);
+		std::fs::remove_file(&path).unwrap();
+	}
+}
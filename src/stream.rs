@@ -0,0 +1,211 @@
+// the async version of ChatReader, for reading chat while yt-dlp is still writing it from inside a tokio program
+// lines go through the same parse_line so both give the same events
+
+use crate::{parse_line, ChatEvent, Error};
+use futures_core::Stream;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::fs::File;
+use tokio::io::{AsyncBufRead, BufReader};
+use tokio::time::Sleep;
+
+// how long to wait before checking a followed file for new data again
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// An async [`Stream`] of chat events, like [`ChatReader`](crate::ChatReader) but over an [`AsyncBufRead`].
+///
+/// A line that fails to parse gives an error and the stream carries on with the next line.
+/// Needs the `async` feature.
+///
+/// ```no_run
+/// # async fn run() -> std::io::Result<()> {
+/// use futures_core::Stream;
+/// use std::pin::Pin;
+///
+/// // stop once nothing was written for ten minutes
+/// let mut events = superchat_extractor::ChatStream::follow("stream.live_chat.json.part", Some(std::time::Duration::from_secs(600))).await?;
+/// while let Some(event) = std::future::poll_fn(|cx| Pin::new(&mut events).poll_next(cx)).await {
+///     println!("{:?}", event);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct ChatStream<R> {
+	reader: R,
+	line: Vec<u8>,
+	line_number: usize,
+	pending: VecDeque<ChatEvent>,
+	follow: Option<Follow>,
+}
+
+// state for waiting on a file that is still being written
+struct Follow {
+	path: PathBuf,
+	idle_timeout: Option<Duration>,
+	last_data: Instant,
+	finished: bool,
+	sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl<R: AsyncBufRead + Unpin> ChatStream<R> {
+	/// Reads until the end of `reader`.
+	pub fn new(reader: R) -> ChatStream<R> {
+		ChatStream { reader, line: Vec::new(), line_number: 0, pending: VecDeque::new(), follow: None }
+	}
+
+	/// The line the last event or error came from, starting at 1.
+	pub fn line_number(&self) -> usize {
+		self.line_number
+	}
+
+	// fills self.line up to the next newline, Ready(None) once there are no more lines
+	// it can return Pending halfway through a line, what was read so far stays in self.line
+	fn poll_line(&mut self, cx: &mut Context<'_>) -> Poll<Option<std::io::Result<()>>> {
+		loop {
+			if let Some(sleep) = self.follow.as_mut().and_then(|follow| follow.sleep.as_mut()) {
+				ready!(sleep.as_mut().poll(cx));
+				if let Some(follow) = self.follow.as_mut() {
+					follow.sleep = None;
+				}
+			}
+
+			let buffer = match ready!(Pin::new(&mut self.reader).poll_fill_buf(cx)) {
+				Ok(buffer) => buffer,
+				Err(e) => return Poll::Ready(Some(Err(e))),
+			};
+
+			if !buffer.is_empty() {
+				if let Some(follow) = self.follow.as_mut() {
+					follow.last_data = Instant::now();
+				}
+				let (read, complete) = match buffer.iter().position(|byte| *byte == b'\n') {
+					Some(end) => (end + 1, true),
+					None => (buffer.len(), false),
+				};
+				self.line.extend_from_slice(&buffer[..read]);
+				Pin::new(&mut self.reader).consume(read);
+				if complete {
+					return Poll::Ready(Some(Ok(())));
+				}
+				continue;
+			}
+
+			// reached the end of what has been written so far
+			let follow = match self.follow.as_mut() {
+				Some(follow) if !follow.finished => follow,
+				// the last line might not end with a newline
+				_ => return Poll::Ready((!self.line.is_empty()).then_some(Ok(()))),
+			};
+			// yt-dlp renames the .part file once the download is done
+			// our handle still points at the data so read whatever is left and stop
+			if !follow.path.exists() {
+				follow.finished = true;
+				continue;
+			}
+			if let Some(timeout) = follow.idle_timeout {
+				if follow.last_data.elapsed() >= timeout {
+					follow.finished = true;
+					continue;
+				}
+			}
+			follow.sleep = Some(Box::pin(tokio::time::sleep(POLL_INTERVAL)));
+		}
+	}
+}
+
+impl ChatStream<BufReader<File>> {
+	/// Follows a chat file yt-dlp is still writing, waiting for new lines until the file is renamed
+	/// when the download finishes or nothing was written for `idle_timeout`.
+	///
+	/// Uses tokio timers so it has to run on a runtime with time enabled.
+	pub async fn follow(path: impl AsRef<Path>, idle_timeout: Option<Duration>) -> std::io::Result<ChatStream<BufReader<File>>> {
+		let path = path.as_ref().to_path_buf();
+		let file = File::open(&path).await?;
+		let mut stream = ChatStream::new(BufReader::new(file));
+		stream.follow = Some(Follow { path, idle_timeout, last_data: Instant::now(), finished: false, sleep: None });
+		Ok(stream)
+	}
+}
+
+impl<R: AsyncBufRead + Unpin> Stream for ChatStream<R> {
+	type Item = Result<ChatEvent, Error>;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let this = self.get_mut();
+		loop {
+			if let Some(event) = this.pending.pop_front() {
+				return Poll::Ready(Some(Ok(event)));
+			}
+			match ready!(this.poll_line(cx)) {
+				Some(Ok(())) => {},
+				Some(Err(e)) => return Poll::Ready(Some(Err(e.into()))),
+				None => return Poll::Ready(None),
+			}
+			this.line_number += 1;
+			let line = std::mem::take(&mut this.line);
+			let line = match std::str::from_utf8(&line) {
+				Ok(line) => line.trim_end_matches(['\n', '\r']),
+				Err(e) => return Poll::Ready(Some(Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e).into()))),
+			};
+			// a file that ends with an empty line is still fine
+			if line.trim().is_empty() {
+				continue;
+			}
+			match parse_line(line) {
+				Ok(events) => this.pending.extend(events),
+				Err(e) => return Poll::Ready(Some(Err(e))),
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tokio::io::AsyncWriteExt;
+
+	const DELETION: &str = r#"{"replayChatItemAction": {"actions": [{"removeChatItemAction": {"targetItemId": "abc"}}]}}"#;
+
+	async fn next<R: AsyncBufRead + Unpin>(stream: &mut ChatStream<R>) -> Option<Result<ChatEvent, Error>> {
+		std::future::poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)).await
+	}
+
+	#[tokio::test]
+	async fn reads_lines_and_carries_on_after_errors() {
+		let input = format!("{DELETION}\r\nnot json\n\n{DELETION}");
+		let mut stream = ChatStream::new(input.as_bytes());
+		assert!(matches!(next(&mut stream).await, Some(Ok(ChatEvent::MessageDeleted(_)))));
+		assert!(matches!(next(&mut stream).await, Some(Err(Error::Json(_)))));
+		assert!(matches!(next(&mut stream).await, Some(Ok(ChatEvent::MessageDeleted(_)))));
+		assert_eq!(stream.line_number(), 4);
+		assert!(next(&mut stream).await.is_none());
+	}
+
+	#[tokio::test]
+	async fn follows_a_growing_file() {
+		let path = std::env::temp_dir().join(format!("superchat-extractor-stream-{}.live_chat.json.part", std::process::id()));
+		let mut file = tokio::fs::File::create(&path).await.unwrap();
+		// half a line first, the stream has to wait for the rest
+		let (start, end) = DELETION.split_at(20);
+		file.write_all(start.as_bytes()).await.unwrap();
+		file.flush().await.unwrap();
+
+		let mut stream = ChatStream::follow(&path, None).await.unwrap();
+		let writer = tokio::spawn(async move {
+			tokio::time::sleep(Duration::from_millis(100)).await;
+			file.write_all(format!("{end}\n{DELETION}\n").as_bytes()).await.unwrap();
+			file.flush().await.unwrap();
+		});
+		assert!(matches!(next(&mut stream).await, Some(Ok(ChatEvent::MessageDeleted(_)))));
+		assert!(matches!(next(&mut stream).await, Some(Ok(ChatEvent::MessageDeleted(_)))));
+		writer.await.unwrap();
+
+		// yt-dlp renaming the .part file ends the stream
+		std::fs::remove_file(&path).unwrap();
+		assert!(next(&mut stream).await.is_none());
+	}
+}
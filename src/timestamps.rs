@@ -0,0 +1,207 @@
+// absolute and video relative times for events
+// youtube gives every event a wall clock timestampUsec, the offset into the video comes with the chat line
+
+use chrono::{DateTime, FixedOffset, Local, SecondsFormat, Utc};
+use clap::ValueEnum;
+
+pub fn utc_from_usec(usec: i64) -> Option<DateTime<Utc>> {
+	DateTime::from_timestamp_micros(usec)
+}
+
+// "2023-11-14T22:13:20.123Z"
+pub fn rfc3339_from_usec(usec: i64) -> Option<String> {
+	utc_from_usec(usec).map(|time| time.to_rfc3339_opts(SecondsFormat::Millis, true))
+}
+
+// yt-dlp writes videoOffsetTimeMsec for every chat line, negative for chat from before the stream started
+// when it's missing the offset is worked out from when the stream started, if the .info.json said
+pub fn video_offset_ms(line_offset: Option<i64>, usec: i64, release_timestamp: Option<i64>) -> Option<i64> {
+	if line_offset.is_some() {
+		return line_offset;
+	}
+	release_timestamp.map(|start| usec / 1000 - start * 1000)
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum TimeFormat {
+	// youtube's own "1:23:45" when the line has one, otherwise the date and time
+	Youtube,
+	// "2023-05-13T17:46:45+00:00"
+	Iso8601,
+	// "2023-05-13T17:46:45.000Z", with milliseconds
+	Rfc3339,
+	// unix seconds
+	Epoch,
+	// "1:23:45" into the video, from videoOffsetTimeMsec
+	Offset,
+}
+
+// there's no timezone database here so only utc, whatever the system uses and fixed offsets
+#[derive(Clone, Copy, Debug)]
+pub enum Timezone {
+	Utc,
+	Local,
+	Fixed(FixedOffset),
+}
+
+// "utc", "local" or an offset like "+09:00", "-0530" or "+2"
+pub fn parse_timezone(text: &str) -> Result<Timezone, String> {
+	match text.to_ascii_lowercase().as_str() {
+		"utc" | "z" => return Ok(Timezone::Utc),
+		"local" => return Ok(Timezone::Local),
+		_ => {},
+	}
+	let invalid = || format!("{text} is not utc, local or an offset like +09:00");
+	let (sign, rest) = if let Some(rest) = text.strip_prefix('+') {
+		(1, rest)
+	} else if let Some(rest) = text.strip_prefix('-') {
+		(-1, rest)
+	} else {
+		return Err(invalid());
+	};
+	let digits: String = rest.chars().filter(|c| *c != ':').collect();
+	if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+		return Err(invalid());
+	}
+	let (hours, minutes) = match digits.len() {
+		1 | 2 => (digits.as_str(), "0"),
+		3 | 4 => digits.split_at(digits.len() - 2),
+		_ => return Err(invalid()),
+	};
+	let seconds = hours.parse::<i32>().map_err(|_| invalid())? * 3600 + minutes.parse::<i32>().map_err(|_| invalid())? * 60;
+	FixedOffset::east_opt(sign * seconds).map(Timezone::Fixed).ok_or_else(invalid)
+}
+
+pub struct TimeFormatter {
+	pub format: TimeFormat,
+	pub timezone: Timezone,
+}
+
+impl TimeFormatter {
+	// the time field of an event, the same way for every event type
+	// timestamp_text is youtube's "1:23:45", only there in replays
+	pub fn format(&self, timestamp_text: Option<String>, usec: i64, offset_ms: Option<i64>) -> String {
+		match self.format {
+			TimeFormat::Youtube => match timestamp_text {
+				Some(text) => text,
+				None => self.date_time(usec).format("%Y-%m-%d %H:%M:%S").to_string(),
+			},
+			TimeFormat::Iso8601 => self.date_time(usec).format("%Y-%m-%dT%H:%M:%S%:z").to_string(),
+			TimeFormat::Rfc3339 => self.date_time(usec).to_rfc3339_opts(SecondsFormat::Millis, true),
+			TimeFormat::Epoch => self.date_time(usec).timestamp().to_string(),
+			// fall back to what youtube showed when the offset isn't known
+			TimeFormat::Offset => match offset_ms {
+				Some(offset) => format_offset(offset),
+				None => TimeFormatter { format: TimeFormat::Youtube, timezone: self.timezone }.format(timestamp_text, usec, None),
+			},
+		}
+	}
+
+	// removals only come with an offset into the video,
+	// the absolute formats need to know when the stream started and show the offset otherwise
+	pub fn format_video_offset(&self, offset_ms: i64, release_timestamp: Option<i64>) -> String {
+		match (self.format, release_timestamp) {
+			(TimeFormat::Youtube | TimeFormat::Offset, _) | (_, None) => format_offset(offset_ms),
+			(_, Some(start)) => self.format(None, (start * 1000 + offset_ms) * 1000, Some(offset_ms)),
+		}
+	}
+
+	fn date_time(&self, usec: i64) -> DateTime<FixedOffset> {
+		let time = utc_from_usec(usec).expect("could not parse timestamp");
+		match self.timezone {
+			Timezone::Utc => time.with_timezone(&FixedOffset::east_opt(0).expect("utc is a valid offset")),
+			Timezone::Local => {
+				let local = time.with_timezone(&Local);
+				local.with_timezone(local.offset())
+			},
+			Timezone::Fixed(offset) => time.with_timezone(&offset),
+		}
+	}
+}
+
+// same shape as youtube's timestampText, "0:05", "1:02:03" or "-0:12"
+pub fn format_offset(offset_ms: i64) -> String {
+	let sign = if offset_ms < 0 { "-" } else { "" };
+	let seconds = offset_ms.unsigned_abs() / 1000;
+	let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+	if hours > 0 {
+		format!("{sign}{hours}:{minutes:02}:{seconds:02}")
+	} else {
+		format!("{sign}{minutes}:{seconds:02}")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// 2023-05-13T17:46:45.123Z
+	const USEC: i64 = 1_684_000_005_123_456;
+
+	#[test]
+	fn utc_times_from_usec() {
+		assert_eq!(rfc3339_from_usec(USEC).as_deref(), Some("2023-05-13T17:46:45.123Z"));
+		assert_eq!(utc_from_usec(USEC).map(|time| time.timestamp()), Some(1_684_000_005));
+	}
+
+	#[test]
+	fn video_offset_prefers_the_line_and_falls_back_to_the_release_time() {
+		assert_eq!(video_offset_ms(Some(5000), USEC, Some(0)), Some(5000));
+		// usec / 1000 - release * 1000, truncating the microseconds
+		assert_eq!(video_offset_ms(None, USEC, Some(1_684_000_000)), Some(5123));
+		// chat from before the stream started
+		assert_eq!(video_offset_ms(None, USEC, Some(1_684_000_010)), Some(-4877));
+		assert_eq!(video_offset_ms(None, USEC, None), None);
+	}
+
+	#[test]
+	fn timezones() {
+		let offset = |text| match parse_timezone(text) {
+			Ok(Timezone::Fixed(offset)) => offset.local_minus_utc(),
+			other => panic!("{text} isn't a fixed offset: {other:?}"),
+		};
+		assert_eq!(offset("+09:00"), 9 * 3600);
+		assert_eq!(offset("-0530"), -(5 * 3600 + 30 * 60));
+		assert_eq!(offset("+2"), 2 * 3600);
+		assert!(matches!(parse_timezone("UTC"), Ok(Timezone::Utc)));
+		assert!(matches!(parse_timezone("local"), Ok(Timezone::Local)));
+		for invalid in ["09:00", "+", "+12345", "+ab", "EST", "+99"] {
+			assert!(parse_timezone(invalid).is_err(), "{invalid}");
+		}
+	}
+
+	#[test]
+	fn every_time_format() {
+		let format = |format, timezone, text: Option<&str>, offset_ms| {
+			TimeFormatter { format, timezone }.format(text.map(String::from), USEC, offset_ms)
+		};
+		let tokyo = parse_timezone("+09:00").unwrap();
+		assert_eq!(format(TimeFormat::Youtube, Timezone::Utc, Some("1:02"), None), "1:02");
+		assert_eq!(format(TimeFormat::Youtube, tokyo, None, None), "2023-05-14 02:46:45");
+		assert_eq!(format(TimeFormat::Iso8601, tokyo, Some("1:02"), None), "2023-05-14T02:46:45+09:00");
+		assert_eq!(format(TimeFormat::Rfc3339, Timezone::Utc, None, None), "2023-05-13T17:46:45.123Z");
+		assert_eq!(format(TimeFormat::Rfc3339, tokyo, None, None), "2023-05-14T02:46:45.123+09:00");
+		assert_eq!(format(TimeFormat::Epoch, tokyo, None, None), "1684000005");
+		assert_eq!(format(TimeFormat::Offset, Timezone::Utc, Some("1:02"), Some(3_723_000)), "1:02:03");
+		// without an offset it's the youtube format
+		assert_eq!(format(TimeFormat::Offset, Timezone::Utc, Some("1:02"), None), "1:02");
+		assert_eq!(format(TimeFormat::Offset, Timezone::Utc, None, None), "2023-05-13 17:46:45");
+	}
+
+	#[test]
+	fn offsets_look_like_youtube_timestamps() {
+		let cases = [(0, "0:00"), (5_999, "0:05"), (65_000, "1:05"), (3_723_000, "1:02:03"), (-12_000, "-0:12"), (-3_723_000, "-1:02:03")];
+		for (offset_ms, expected) in cases {
+			assert_eq!(format_offset(offset_ms), expected, "{offset_ms}");
+		}
+	}
+
+	#[test]
+	fn removal_times_from_the_video_offset() {
+		let formatter = |format| TimeFormatter { format, timezone: parse_timezone("+09:00").unwrap() };
+		assert_eq!(formatter(TimeFormat::Youtube).format_video_offset(65_000, Some(1_684_000_000)), "1:05");
+		assert_eq!(formatter(TimeFormat::Rfc3339).format_video_offset(65_000, Some(1_684_000_000)), "2023-05-14T02:47:45.000+09:00");
+		assert_eq!(formatter(TimeFormat::Epoch).format_video_offset(-5_000, Some(1_684_000_000)), "1683999995");
+		assert_eq!(formatter(TimeFormat::Iso8601).format_video_offset(65_000, None), "1:05");
+	}
+}
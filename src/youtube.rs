@@ -0,0 +1,366 @@
+// the json yt-dlp writes for every chat line, field names are youtube's
+// only parse.rs looks at these, everything else gets the events from event.rs
+#![allow(non_snake_case)]
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+pub struct ChatItem {
+    //isLive: Option<bool>,
+    // live captures put the offset here, replays inside replayChatItemAction
+    pub videoOffsetTimeMsec: Option<String>,
+    pub replayChatItemAction: ReplayChatItemAction,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReplayChatItemAction {
+    pub actions: Vec<Action>,
+    pub videoOffsetTimeMsec: Option<String>,
+}
+
+
+// variants are named after the json key they hold without the Action suffix
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum Action {
+    AddChatItem {
+        // by far the biggest payload, boxed so every other action stays small
+        addChatItemAction: Box<AddChatItemAction>,
+        clickTrackingParams: Option<String>,
+    },
+	AddBannerToLiveChatCommand {
+		addBannerToLiveChatCommand: serde_json::Value,
+		clickTrackingParams: Option<String>,
+	},
+	AddLiveChatTickerItem {
+		addLiveChatTickerItemAction: serde_json::Value,
+		clickTrackingParams: Option<String>,
+	},
+	CloseLiveChatActionPanel {
+		closeLiveChatActionPanelAction: serde_json::Value,
+		clickTrackingParams: Option<String>,
+	},
+	LiveChatReportModerationStateCommand {
+		liveChatReportModerationStateCommand: serde_json::Value,
+		clickTrackingParams: Option<String>,
+	},
+	RemoveBannerForLiveChatCommand {
+		removeBannerForLiveChatCommand: serde_json::Value,
+		clickTrackingParams: Option<String>,
+	},
+	RemoveChatItem {
+		removeChatItemAction: RemoveChatItem,
+		clickTrackingParams: Option<String>,
+	},
+	RemoveChatItemByAuthor {
+		removeChatItemByAuthorAction: RemoveChannelItem,
+		clickTrackingParams: Option<String>,
+	},
+	ReplaceChatItem {
+		replaceChatItemAction: serde_json::Value,
+		clickTrackingParams: Option<String>,
+	},
+	ShowLiveChatActionPanel {
+		showLiveChatActionPanelAction: serde_json::Value,
+		clickTrackingParams: Option<String>,
+	},
+	UpdateLiveChatPoll {
+		updateLiveChatPollAction: UpdateLiveChatPollAction,
+		clickTrackingParams: Option<String>,
+	},
+	ReplaceLiveChatRenderer {
+		replaceLiveChatRendererAction: serde_json::Value,
+		clickTrackingParams: Option<String>,
+	},
+    Unknown(serde_json::Value),
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RemoveChatItem {
+	pub targetItemId: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RemoveChannelItem {
+	pub externalChannelId: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum ChatItemType {
+	LiveChatMembershipItemRenderer {
+		liveChatMembershipItemRenderer: LiveChatMembershipItemRenderer,
+	},
+	LiveChatPaidMessageRenderer {
+		liveChatPaidMessageRenderer: PaidMessage,
+	},
+	LiveChatPlaceholderItemRenderer {
+		liveChatPlaceholderItemRenderer: serde_json::Value,
+	},
+	LiveChatSponsorshipsGiftPurchaseAnnouncementRenderer {
+		liveChatSponsorshipsGiftPurchaseAnnouncementRenderer: GiftPurchase,
+	},
+	LiveChatSponsorshipsGiftRedemptionAnnouncementRenderer {
+		liveChatSponsorshipsGiftRedemptionAnnouncementRenderer: GiftRedemptionAnnouncement,
+	},
+	LiveChatTextMessageRenderer {
+		liveChatTextMessageRenderer: LiveChatTextMessage,
+	},
+	LiveChatViewerEngagementMessageRenderer {
+		liveChatViewerEngagementMessageRenderer: serde_json::Value,
+	},
+	LiveChatPaidStickerRenderer{
+		liveChatPaidStickerRenderer: PaidSticker,
+	},
+	LiveChatModeChangeMessageRenderer{
+		liveChatModeChangeMessageRenderer: serde_json::Value,
+	},
+	Unknown(serde_json::Value)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LiveChatTextMessage {
+	pub authorBadges: Option<Vec<AuthorBadges>>,
+	pub authorExternalChannelId: String,
+	pub authorName: SimpleText,
+	pub authorPhoto: AuthorPhotos,
+	pub contextMenuAccessibility: serde_json::Value,
+	pub contextMenuEndpoint: serde_json::Value,
+	pub id: String,
+	pub message: RunsContainer,
+	pub timestampText: Option<SimpleText>,
+	pub timestampUsec: String,
+	pub trackingParams: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PaidSticker {
+	pub authorExternalChannelId: String,
+	pub authorName: SimpleText,
+	pub authorNameTextColor: i64,
+	pub authorPhoto: AuthorPhotos,
+	pub backgroundColor: i64,
+	pub contextMenuAccessibility: serde_json::Value,
+	pub contextMenuEndpoint: serde_json::Value,
+	pub id: String,
+	pub moneyChipBackgroundColor: i64,
+	pub moneyChipTextColor: i64,
+	pub purchaseAmountText: SimpleText,
+	pub sticker: PollImage,
+	pub stickerDisplayHeight: i64,
+	pub stickerDisplayWidth: i64,
+	pub timestampText: Option<SimpleText>,
+	pub timestampUsec: String,
+	pub trackingParams: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GiftRedemptionAnnouncement {
+	pub authorExternalChannelId: String,
+	pub authorName: SimpleText,
+	pub authorPhoto: AuthorPhotos,
+	pub contextMenuAccessibility: serde_json::Value,
+	pub contextMenuEndpoint: serde_json::Value,
+	pub id: String,
+	pub message: RunsContainer,
+	pub timestampText: Option<SimpleText>,
+	pub timestampUsec: String,
+	pub trackingParams: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GiftPurchase {
+	pub authorExternalChannelId: String,
+	pub header: GiftPurchaseHeader,
+	pub id: String,
+	pub timestampUsec: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GiftPurchaseHeader {
+	pub liveChatSponsorshipsHeaderRenderer: GiftPurchaseHeaderRenderer,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AuthorBadges {
+	pub liveChatAuthorBadgeRenderer: LiveChatAuthorBadgeRenderer,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LiveChatAuthorBadgeRenderer {
+	pub customThumbnail: Option<AuthorPhotos>,
+	pub accessibility: EmoteAccessability,
+	pub tooltip: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GiftPurchaseHeaderRenderer {
+	pub authorBadges: Option<Vec<AuthorBadges>>,
+	pub authorName: SimpleText,
+	pub authorPhoto: AuthorPhotos,
+	pub contextMenuAccessibility: serde_json::Value,
+	pub contextMenuEndpoint: serde_json::Value,
+	pub image: serde_json::Value,
+	pub primaryText: RunsContainer,
+}
+
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LiveChatMembershipItemRenderer {
+	pub authorBadges: Option<Vec<AuthorBadges>>,
+	pub authorExternalChannelId: String,
+	pub authorName: SimpleText,
+	pub authorPhoto: AuthorPhotos,
+	pub contextMenuAccessibility: serde_json::Value,
+	pub contextMenuEndpoint: serde_json::Value,
+	pub headerPrimaryText: Option<RunsContainer>,
+	pub headerSubtext: HeaderSubtextType,
+	pub id: String,
+	pub message: Option<RunsContainer>,
+	pub timestampText: Option<SimpleText>,
+	pub timestampUsec: String,
+	pub trackingParams: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum HeaderSubtextType {
+	Runs(RunsContainer),
+	SimpleText(SimpleText),
+
+}
+
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PaidMessage {
+	pub authorBadges: Option<Vec<AuthorBadges>>,
+	pub authorExternalChannelId: String,
+	pub authorName: SimpleText,
+	pub authorNameTextColor: i64,
+	pub authorPhoto: AuthorPhotos,
+	pub bodyBackgroundColor: i64,
+	pub bodyTextColor: i64,
+	//contextMenuAccessibility: serde_json::Value,
+	//contextMenuEndpoint: serde_json::Value,
+	pub headerBackgroundColor: i64,
+	pub headerTextColor: i64,
+	pub id: String,
+	pub message: Option<RunsContainer>,
+	pub purchaseAmountText: SimpleText,
+	pub textInputBackgroundColor: i64,
+	pub timestampText: Option<SimpleText>,
+	pub timestampColor: i64,
+	pub timestampUsec: String,
+	//trackingParams: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AuthorPhotos {
+	pub thumbnails: Vec<Thumbnail>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Thumbnail {
+	pub height: Option<i64>,
+	pub url: String,
+	pub width: Option<i64>
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AddChatItemAction {
+	pub item: ChatItemType,
+	pub clientId: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UpdateLiveChatPollAction {
+	pub pollToUpdate: PollToUpdate
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PollToUpdate {
+	pub pollRenderer: PollRenderer
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PollRenderer {
+	pub choices:Vec<PollChoice>,
+	pub liveChatPollId:String,
+	pub header:PollHeader,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PollHeader {
+	pub pollHeaderRenderer: PollHeaderRenderer
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PollHeaderRenderer {
+	pub pollQuestion: RunsContainer,
+	//thubnail: PollThumbnail,
+	pub metadataText: RunsContainer,
+	pub liveChatPollType: String,
+	//contextMenuButton: PollContextMenuButton,
+}
+
+
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RunsContainer {
+	pub runs: Vec<RunsTypes>,
+	pub bold: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PollChoice {
+	pub selected:bool,
+	pub text: RunsContainer,
+	pub votePercentage: SimpleText,
+	pub voteRatio:f32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum RunsTypes {
+	Text{
+		text:String,
+		italics:Option<bool>,
+		bold:Option<bool>,
+	},
+	Emoji{
+		emoji:PollRunsEmoji
+	},
+	Unknown(serde_json::Value)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PollRunsEmoji {
+	pub emojiId:String,
+	pub image:PollImage,
+	pub searchTerms:Option<Vec<String>>,
+	pub shortcuts:Option<Vec<String>>,
+	pub isCustomEmoji:Option<bool>,
+	pub variantIds:Option<Vec<String>>,
+	pub supportsSkinTone:Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PollImage {
+	pub accessibility: EmoteAccessability,
+	pub thumbnails: Vec<Thumbnail>
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EmoteAccessability {
+	pub accessibilityData: AccessibilityData
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AccessibilityData {
+	pub label: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SimpleText {
+	pub simpleText:String
+}